@@ -35,29 +35,95 @@ pub fn get_struct_name_from_impl(input: &syn::ItemImpl) -> syn::Ident {
     return struct_name.clone();
 }
 
-/// Get the names of the variables to a function omitting self
+/// Get the self receiver of a function if it has one
 ///
 /// # Examples
 /// ```
-/// fn foo(a: f64, b: Vec<i32>) -> ...
-/// > [a, b]
+/// fn foo(&mut self, a: f64) -> ...
+/// > Some(&mut self)
 /// ```
-pub fn get_function_argument_names(input: &syn::ImplItemFn) -> Vec<&syn::Pat> {
-    input
-        .sig
-        .inputs
-        .iter()
-        .filter_map(|arg| {
-            if let syn::FnArg::Typed(pat_type) = arg {
-                Some(&*pat_type.pat)
-            // } else if let syn::FnArg::Receiver(receiver) = arg {
-            //     // Handle `&self` or `self`
-            //     receiver.pat.as_ref().map(|p| &**p)
-            } else {
-                None
-            }
-        })
-        .collect()
+pub fn get_function_receiver(input: &syn::ImplItemFn) -> Option<&syn::Receiver> {
+    match input.sig.inputs.first() {
+        Some(syn::FnArg::Receiver(receiver)) => Some(receiver),
+        _ => None,
+    }
+}
+
+/// Replace any `Self` in a type with the given struct name
+///
+/// Needed when moving a method signature into another impl block, where `Self` would refer to the new type
+///
+/// # Examples
+/// ```
+/// Option<Self>, Foo
+/// > Option<Foo>
+/// ```
+pub fn replace_self_in_type(ty: &syn::Type, struct_name: &syn::Ident) -> syn::Type {
+    fn replace(
+        tokens: proc_macro2::TokenStream,
+        struct_name: &syn::Ident,
+    ) -> proc_macro2::TokenStream {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                proc_macro2::TokenTree::Ident(ident) if ident == "Self" => {
+                    proc_macro2::TokenTree::Ident(struct_name.clone())
+                }
+                proc_macro2::TokenTree::Group(group) => {
+                    let mut new_group = proc_macro2::Group::new(
+                        group.delimiter(),
+                        replace(group.stream(), struct_name),
+                    );
+                    new_group.set_span(group.span());
+                    proc_macro2::TokenTree::Group(new_group)
+                }
+                other => other,
+            })
+            .collect()
+    }
+    syn::parse2(replace(quote::quote!(#ty), struct_name)).expect("Failed to replace Self in type")
+}
+
+/// Determine if a type is exactly the given ident
+///
+/// # Examples
+/// ```
+/// Foo, Foo
+/// > true
+/// Vec<Foo>, Foo
+/// > false
+/// ```
+pub fn type_is_ident(ty: &syn::Type, ident: &syn::Ident) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path.qself.is_none() && type_path.path.is_ident(ident),
+        _ => false,
+    }
+}
+
+/// Get the `T` of a `PyResult<T>` or `Result<T, E>` type
+///
+/// # Examples
+/// ```
+/// PyResult<f64>
+/// > Some(f64)
+/// Vec<f64>
+/// > None
+/// ```
+pub fn get_result_ok_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let last_seg = type_path.path.segments.last()?;
+    if last_seg.ident != "PyResult" && last_seg.ident != "Result" {
+        return None;
+    }
+    match &last_seg.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(ok_ty)) => Some(ok_ty),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
 /// Determine if this function has a given attribute tag
@@ -67,3 +133,18 @@ pub fn fn_has_attr_name(item: &syn::ImplItemFn, target_attr_name: &str) -> bool
         .iter()
         .any(|attr: &syn::Attribute| attr.path().is_ident(target_attr_name))
 }
+
+/// Remove all attributes with a given name
+/// ex: #[py_bevy(skip)]
+pub fn remove_attrs_named(attrs: &mut Vec<syn::Attribute>, target_attr_name: &str) {
+    attrs.retain(|attr| !attr.path().is_ident(target_attr_name));
+}
+
+/// Get all doc comment attributes
+/// ex: /// Some docs => #[doc = " Some docs"]
+pub fn get_doc_attrs(attrs: &[syn::Attribute]) -> Vec<&syn::Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .collect()
+}
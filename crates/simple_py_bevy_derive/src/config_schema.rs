@@ -0,0 +1,231 @@
+use crate::rename_rule::RenameRule;
+use quote::quote;
+
+// The parts of #[serde(...)] that change what a config file looks like
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<RenameRule>,
+    // Some(None) is #[serde(default)], Some(Some(path)) is #[serde(default = "path")]
+    default: Option<Option<syn::Path>>,
+    skip: bool,
+    flatten: bool,
+}
+
+fn parse_serde_attrs(attrs: &[syn::Attribute]) -> syn::Result<SerdeAttrs> {
+    let mut serde = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            let key = meta
+                .path
+                .get_ident()
+                .map(|i| i.to_string())
+                .unwrap_or_default();
+            match key.as_str() {
+                "rename" if meta.input.peek(syn::Token![=]) => {
+                    serde.rename = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                }
+                "rename_all" if meta.input.peek(syn::Token![=]) => {
+                    let rule = meta.value()?.parse::<syn::LitStr>()?;
+                    serde.rename_all =
+                        Some(RenameRule::from_str(&rule.value()).ok_or_else(|| {
+                            syn::Error::new_spanned(&rule, "unknown rename_all rule")
+                        })?);
+                }
+                "default" if meta.input.peek(syn::Token![=]) => {
+                    let path = meta.value()?.parse::<syn::LitStr>()?.parse()?;
+                    serde.default = Some(Some(path));
+                }
+                "default" => serde.default = Some(None),
+                "skip" | "skip_deserializing" => serde.skip = true,
+                "flatten" => serde.flatten = true,
+                // these don't change what serde reads
+                "deny_unknown_fields"
+                | "skip_serializing"
+                | "skip_serializing_if"
+                | "serialize_with"
+                | "bound"
+                | "borrow"
+                | "crate"
+                | "expecting" => {
+                    if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<syn::Expr>()?;
+                    } else if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|nested| {
+                            nested.value()?.parse::<syn::Expr>()?;
+                            Ok(())
+                        })?;
+                    }
+                }
+                // anything else, ex: alias, rename(deserialize = "..."), with or tag,
+                // would make the schema disagree with what serde reads
+                _ if meta.input.peek(syn::token::Paren) => {
+                    return Err(meta.error(format!(
+                        "ConfigSchema doesn't support #[serde({}(...))]",
+                        key
+                    )));
+                }
+                _ => {
+                    return Err(
+                        meta.error(format!("ConfigSchema doesn't support #[serde({})]", key))
+                    );
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(serde)
+}
+
+fn doc_string(attrs: &[syn::Attribute]) -> proc_macro2::TokenStream {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .collect();
+    match lines.is_empty() {
+        true => quote! { None },
+        false => {
+            let doc = lines.join("\n");
+            quote! { Some(#doc) }
+        }
+    }
+}
+
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Implement ConfigSchema for a struct or unit enum, following its serde attributes
+pub(crate) fn config_schema_impl(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let container = parse_serde_attrs(&ast.attrs)?;
+    let description = doc_string(&ast.attrs);
+
+    let schema = match &ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => {
+            // a struct level #[serde(default)] fills missing fields from Self::default()
+            let struct_defaults = match container.default {
+                Some(_) => {
+                    quote! { let defaults = simple_py_bevy::config_schema_default::<Self>(); }
+                }
+                None => quote! {},
+            };
+
+            let mut schema_fields = Vec::new();
+            for field in &fields.named {
+                let serde = parse_serde_attrs(&field.attrs)?;
+                if serde.skip {
+                    continue;
+                }
+                let ident = field.ident.as_ref().unwrap().to_string();
+                let field_name = match (&serde.rename, &container.rename_all) {
+                    (Some(rename), _) => rename.clone(),
+                    (None, Some(rule)) => rule.apply_to_field(&ident),
+                    (None, None) => ident,
+                };
+
+                let ty = &field.ty;
+                let default = match (&serde.default, &container.default) {
+                    (Some(Some(path)), _) => {
+                        quote! { Some(simple_py_bevy::config_schema_value(&#path())) }
+                    }
+                    (Some(None), _) => {
+                        quote! { Some(simple_py_bevy::config_schema_default::<#ty>()) }
+                    }
+                    (None, Some(_)) => quote! { defaults.get(#field_name).cloned() },
+                    (None, None) => quote! { None },
+                };
+                let required =
+                    serde.default.is_none() && container.default.is_none() && !is_option(ty);
+                let field_description = doc_string(&field.attrs);
+                let flatten = serde.flatten;
+
+                schema_fields.push(quote! {
+                    simple_py_bevy::SchemaField {
+                        name: #field_name,
+                        schema: <#ty as simple_py_bevy::ConfigSchema>::config_schema(),
+                        description: #field_description,
+                        default: #default,
+                        required: #required,
+                        flatten: #flatten,
+                    }
+                });
+            }
+
+            quote! {
+                #struct_defaults
+                simple_py_bevy::struct_config_schema(#description, vec![#(#schema_fields),*])
+            }
+        }
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Unnamed(fields),
+            ..
+        }) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            quote! {
+                simple_py_bevy::newtype_config_schema(
+                    #description,
+                    <#ty as simple_py_bevy::ConfigSchema>::config_schema(),
+                )
+            }
+        }
+        syn::Data::Enum(data) => {
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, syn::Fields::Unit) {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        "ConfigSchema only supports enums with unit variants",
+                    ));
+                }
+                let serde = parse_serde_attrs(&variant.attrs)?;
+                if serde.skip {
+                    continue;
+                }
+                let ident = variant.ident.to_string();
+                variants.push(match (serde.rename, &container.rename_all) {
+                    (Some(rename), _) => rename,
+                    (None, Some(rule)) => rule.apply_to_variant(&ident),
+                    (None, None) => ident,
+                });
+            }
+            quote! { simple_py_bevy::enum_config_schema(#description, &[#(#variants),*]) }
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "ConfigSchema supports structs with named fields, newtypes and unit enums",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics simple_py_bevy::ConfigSchema for #name #ty_generics #where_clause {
+            fn config_schema() -> simple_py_bevy::ConfigValue {
+                #schema
+            }
+        }
+    })
+}
@@ -1,65 +1,223 @@
 extern crate proc_macro;
 extern crate quote;
 use crate::backend;
-use darling::FromField;
+use crate::rename_rule::RenameRule;
+use darling::{FromAttributes, FromDeriveInput, FromField};
 use quote::{format_ident, quote};
 use syn::ItemImpl;
 
-fn wrap_py_method_with_get_inner(method: &syn::ImplItemFn) -> syn::ImplItemFn {
+/// Method level options for #[py_bevy_methods] and #[py_ref_methods]
+#[derive(Debug, Default, FromAttributes)]
+#[darling(attributes(py_bevy))]
+struct PyMethodAttrs {
+    // Don't expose this method on the reference class
+    #[darling(default)]
+    skip: bool,
+}
+
+/// Make the arguments of a copied signature forwardable to the original struct's method
+/// Returns the expressions to call the original method with
+///
+/// `Self` is the reference class in the new impl, so point it back at the original struct.
+/// Arguments taking the original struct by value also accept any of its reference classes
+///
+/// ```
+/// fn foo(&self, mut a: f64, other: Self)
+/// > fn foo(&self, a: f64, other: simple_py_bevy::OwnedOrRef<Foo, FooRef>)
+/// > [a, other.0]
+/// ```
+fn forward_signature_args(
+    sig: &mut syn::Signature,
+    struct_name: &syn::Ident,
+    py_ref_name: &syn::Ident,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let mut call_args = Vec::new();
+    for arg in sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            let arg_name = match &mut *pat_type.pat {
+                syn::Pat::Ident(pat_ident) => {
+                    pat_ident.by_ref = None;
+                    pat_ident.mutability = None;
+                    pat_ident.ident.clone()
+                }
+                // pyo3 rejects these too, since it needs a name to expose to python
+                other => return Err(syn::Error::new_spanned(other, "expected argument name")),
+            };
+            let arg_ty = backend::replace_self_in_type(&pat_type.ty, struct_name);
+
+            if backend::type_is_ident(&arg_ty, struct_name) {
+                *pat_type.ty =
+                    syn::parse_quote! { simple_py_bevy::OwnedOrRef<#arg_ty, #py_ref_name> };
+                call_args.push(quote! { #arg_name.0 });
+            } else {
+                *pat_type.ty = arg_ty;
+                call_args.push(quote! { #arg_name });
+            }
+        }
+    }
+    Ok(call_args)
+}
+
+/// Forward a #[staticmethod] or function #[classattr] to the original struct
+///
+/// ```
+/// #[staticmethod]
+/// fn foo(a: f64) -> Self
+/// > #[staticmethod]
+/// > fn foo(a: f64) -> Foo { Foo::foo(a) }
+/// ```
+fn forward_static_method(
+    method: &syn::ImplItemFn,
+    struct_name: &syn::Ident,
+    py_ref_name: &syn::Ident,
+) -> syn::Result<syn::ImplItemFn> {
+    let mut new_method = method.clone();
+    let old_sig_name = &method.sig.ident;
+
+    let call_args = forward_signature_args(&mut new_method.sig, struct_name, py_ref_name)?;
+    if let syn::ReturnType::Type(_, ty) = &mut new_method.sig.output {
+        **ty = backend::replace_self_in_type(ty, struct_name);
+    }
+    new_method.block = syn::parse_quote!(
+        {
+            #struct_name::#old_sig_name(#(#call_args),*)
+        }
+    );
+    Ok(new_method)
+}
+
+/// Forward a #[classmethod] to the original struct, passing the original struct's type as cls
+///
+/// ```
+/// #[classmethod]
+/// fn foo(cls: &Bound<'_, PyType>, a: f64) -> Self
+/// > #[classmethod]
+/// > fn foo(cls: &Bound<'_, PyType>, a: f64) -> Foo { Foo::foo(&cls.py().get_type::<Foo>(), a) }
+/// ```
+fn forward_class_method(
+    method: &syn::ImplItemFn,
+    struct_name: &syn::Ident,
+    py_ref_name: &syn::Ident,
+) -> syn::Result<syn::ImplItemFn> {
+    let mut new_method = method.clone();
+    let old_sig_name = &method.sig.ident;
+
+    let call_args = forward_signature_args(&mut new_method.sig, struct_name, py_ref_name)?;
+    if let syn::ReturnType::Type(_, ty) = &mut new_method.sig.output {
+        **ty = backend::replace_self_in_type(ty, struct_name);
+    }
+
+    let (cls_name, cls_ty, call_args) = match (method.sig.inputs.first(), call_args.split_first()) {
+        (Some(syn::FnArg::Typed(cls)), Some((cls_name, call_args))) => {
+            (cls_name, &cls.ty, call_args)
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &method.sig,
+                "#[classmethod] needs a cls argument",
+            ));
+        }
+    };
+    let owned_cls = match &**cls_ty {
+        syn::Type::Reference(_) => quote! { &#cls_name.py().get_type::<#struct_name>() },
+        _ => quote! { #cls_name.py().get_type::<#struct_name>() },
+    };
+    new_method.block = syn::parse_quote!(
+        {
+            #struct_name::#old_sig_name(#owned_cls, #(#call_args),*)
+        }
+    );
+    Ok(new_method)
+}
+
+/// Forward a #[classattr] constant to the original struct
+///
+/// ```
+/// #[classattr]
+/// const MAX: f64 = 1.0;
+/// > #[classattr]
+/// > const MAX: f64 = Foo::MAX;
+/// ```
+fn forward_classattr_const(
+    constant: &syn::ImplItemConst,
+    struct_name: &syn::Ident,
+) -> syn::ImplItemConst {
+    let mut new_const = constant.clone();
+    let const_name = &constant.ident;
+
+    new_const.ty = backend::replace_self_in_type(&constant.ty, struct_name);
+    new_const.expr = syn::parse_quote!(#struct_name::#const_name);
+    new_const
+}
+
+/// Wrap a method of the original struct so it can be called from its reference class
+///
+/// pyo3 attributes (signature, name, getter/setter, ...) are kept as is, so the method looks identical from python.
+/// `&self` methods only borrow the inner value immutably so bevy change detection isn't triggered
+fn wrap_py_method_with_get_inner(
+    method: &syn::ImplItemFn,
+    struct_name: &syn::Ident,
+    py_ref_name: &syn::Ident,
+) -> syn::Result<syn::ImplItemFn> {
     let mut new_method = method.clone();
     let old_sig_name = &method.sig.ident;
 
-    let old_arg_names = backend::get_function_argument_names(&method);
+    let receiver = match backend::get_function_receiver(method) {
+        Some(receiver) => receiver,
+        None => {
+            // pyo3 treats the first argument as the receiver (slf: PyRef<Self>, ...)
+            // which can't be created from a reference class
+            return Err(syn::Error::new_spanned(
+                &method.sig,
+                "methods without a &self or &mut self receiver can't be wrapped by a reference class, use #[py_bevy(skip)] to skip it",
+            ));
+        }
+    };
+    let get_inner = if receiver.mutability.is_some() {
+        quote! { self.get_inner_ref_mut()? }
+    } else {
+        quote! { self.get_inner_ref()? }
+    };
+
+    let call_args = forward_signature_args(&mut new_method.sig, struct_name, py_ref_name)?;
 
     match &method.sig.output {
-        // todo: Inputs that match Self should be auto wrapped in Either
-        // fn foo(&self, other: ThisObject) => fn foo(&self, other: Either<ThisObject, ThisObjectBevyRef>)
         syn::ReturnType::Default => {
             new_method.block = syn::parse_quote!(
                 {
-                    self.get_inner_ref_mut()?.#old_sig_name(#(#old_arg_names),*);
+                    #get_inner.#old_sig_name(#(#call_args),*);
                     Ok(())
                 }
             );
             new_method.sig.output = syn::parse2(quote! { -> pyo3::PyResult<()> }).unwrap();
         }
         syn::ReturnType::Type(_, ty) => {
-            let original_r_type = quote! { #ty };
-            let mut transformed_r_type = quote! { -> pyo3::PyResult<#original_r_type> };
-            let mut prop_inner = false;
-
-            if let syn::Type::Path(type_path) = &**ty {
-                // Dont auto wrap return if it's already PyResult<something>
-                if type_path
-                    .path
-                    .segments
-                    .last()
-                    .map(|seg| seg.ident.to_string())
-                    == Some("PyResult".to_string())
-                {
-                    transformed_r_type = quote! { -> #original_r_type };
-                    prop_inner = true;
-                }
-            }
+            let ty = backend::replace_self_in_type(ty, struct_name);
 
-            if prop_inner {
-                new_method.block = syn::parse_quote!(
-                    {
-                        self.get_inner_ref_mut()?.#old_sig_name(#(#old_arg_names),*)
-                    }
-                );
-            } else {
-                new_method.block = syn::parse_quote!(
-                    {
-                        Ok(self.get_inner_ref_mut()?.#old_sig_name(#(#old_arg_names),*))
-                    }
-                );
-            }
+            // Dont auto wrap return if it's already PyResult<something> or Result<something, E>
+            let transformed_r_type = match backend::get_result_ok_type(&ty) {
+                Some(ok_ty) => {
+                    new_method.block = syn::parse_quote!(
+                        {
+                            Ok(#get_inner.#old_sig_name(#(#call_args),*)?)
+                        }
+                    );
+                    quote! { -> pyo3::PyResult<#ok_ty> }
+                }
+                None => {
+                    new_method.block = syn::parse_quote!(
+                        {
+                            Ok(#get_inner.#old_sig_name(#(#call_args),*))
+                        }
+                    );
+                    quote! { -> pyo3::PyResult<#ty> }
+                }
+            };
             new_method.sig.output =
                 syn::parse2(transformed_r_type).expect("Failed to set new return type");
         }
     };
-    new_method
+    Ok(new_method)
 }
 
 pub(crate) fn wrap_all_methods_with_get_inner(
@@ -67,22 +225,65 @@ pub(crate) fn wrap_all_methods_with_get_inner(
     struct_suffix: String,
 ) -> proc_macro2::TokenStream {
     let mut generated_methods = Vec::new();
+    let mut errors = Vec::new();
 
-    let struct_name = backend::get_struct_name_from_impl(&input);
+    let struct_name = backend::get_struct_name_from_impl(input);
     let py_bevy_ref_name = quote::format_ident!("{}{}", struct_name, struct_suffix);
 
     for item in &mut input.items {
         match item {
             syn::ImplItem::Fn(method) => {
-                let fn_has_new = backend::fn_has_attr_name(&method, "new");
-                let fn_has_staticmeth = backend::fn_has_attr_name(&method, "staticmethod");
-                let fn_has_classattr = backend::fn_has_attr_name(&method, "classattr");
-                if fn_has_new || fn_has_staticmeth || fn_has_classattr {
-                    // you cant create a reference from python anyway, so ignore pyo3 constructors and static methods for now (#[new, staticmethod] attributes)
+                let method_attrs = match PyMethodAttrs::from_attributes(&method.attrs) {
+                    Ok(attrs) => attrs,
+                    Err(e) => {
+                        errors.push(e.write_errors());
+                        continue;
+                    }
+                };
+                // #[py_bevy] isn't a real attribute, so it can't be left on the original impl
+                backend::remove_attrs_named(&mut method.attrs, "py_bevy");
+
+                let fn_has_new = backend::fn_has_attr_name(method, "new");
+                let fn_has_staticmeth = backend::fn_has_attr_name(method, "staticmethod");
+                let fn_has_classmeth = backend::fn_has_attr_name(method, "classmethod");
+                let fn_has_classattr = backend::fn_has_attr_name(method, "classattr");
+                if method_attrs.skip || fn_has_new {
+                    // you cant create a reference from python anyway, so ignore pyo3 constructors
                     continue;
                 }
-                let new_method = wrap_py_method_with_get_inner(&method);
-                generated_methods.push(new_method);
+
+                let new_method = if fn_has_staticmeth || fn_has_classattr {
+                    forward_static_method(method, &struct_name, &py_bevy_ref_name)
+                } else if fn_has_classmeth {
+                    forward_class_method(method, &struct_name, &py_bevy_ref_name)
+                } else {
+                    wrap_py_method_with_get_inner(method, &struct_name, &py_bevy_ref_name)
+                };
+                match new_method {
+                    Ok(new_method) => generated_methods.push(syn::ImplItem::Fn(new_method)),
+                    Err(e) => errors.push(e.to_compile_error()),
+                }
+            }
+            syn::ImplItem::Const(constant)
+                if constant
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("classattr")) =>
+            {
+                let new_const = forward_classattr_const(constant, &struct_name);
+                generated_methods.push(syn::ImplItem::Const(new_const));
+            }
+            _ => {}
+        }
+    }
+
+    #[cfg(not(feature = "gen-ref-stubs"))]
+    for method in &mut generated_methods {
+        // #[gen_stub] is only understood when the reference class methods are stubbed as well
+        match method {
+            syn::ImplItem::Fn(method) => backend::remove_attrs_named(&mut method.attrs, "gen_stub"),
+            syn::ImplItem::Const(constant) => {
+                backend::remove_attrs_named(&mut constant.attrs, "gen_stub")
             }
             _ => {}
         }
@@ -91,13 +292,17 @@ pub(crate) fn wrap_all_methods_with_get_inner(
     let functions = quote! {
         #(#generated_methods)*
     };
+    let stub_gen_attr = gen_stub_pymethods_attr();
+
+    quote! {
+        #(#errors)*
 
-    proc_macro2::TokenStream::from(quote! {
+        #stub_gen_attr
         #[pyo3::pymethods]
         impl #py_bevy_ref_name {
             #functions
         }
-    })
+    }
 }
 
 #[allow(dead_code)]
@@ -146,6 +351,251 @@ pub(crate) fn export_downcast_into_py_any(struct_name: &syn::Ident) -> proc_macr
     .into()
 }
 
+/// Struct level options for the generated reference classes
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(py_bevy))]
+pub(crate) struct PyRefStructAttrs {
+    // Python name to use instead of the struct name, the reference classes are still suffixed with BevyRef/Ref
+    #[darling(default)]
+    class_name: Option<String>,
+    // Rename all field accessors, ex: rename_all = "camelCase"
+    #[darling(default)]
+    rename_all: Option<RenameRule>,
+    // Generate __repr__ from the Debug impl
+    #[darling(default)]
+    repr: bool,
+    // Generate __eq__ from the PartialEq impl, comparing against either an owned value or a reference
+    #[darling(default)]
+    eq: bool,
+    // Generate __hash__ from the Hash impl
+    #[darling(default)]
+    hash: bool,
+    // Generate __copy__ and __deepcopy__ that return an owned value
+    #[darling(default)]
+    copy: bool,
+    // Module to put the reference class stubs in, should match the owned class
+    #[darling(default)]
+    #[cfg_attr(not(feature = "gen-ref-stubs"), allow(dead_code))]
+    stub_gen_module: Option<String>,
+    // Methods of a python API (ex: PyEntity::get) that take this struct's python class and return its reference class
+    // Stubbed as typing.overloads so type checkers can follow registry driven access
+    #[darling(default)]
+    #[cfg_attr(not(feature = "gen-ref-stubs"), allow(dead_code))]
+    ref_overloads: darling::util::PathList,
+    // fn(&Self) -> PyResult<()> run against the updated struct before any generated setter writes it
+    #[darling(default)]
+    validate: Option<syn::Path>,
+    // Read only python properties computed by fn(&Self) -> T, named after the function
+    #[darling(default, multiple)]
+    property: Vec<syn::Path>,
+    // Generate to_dict and from_dict on the owned and reference classes, skipped fields need Default
+    #[darling(default)]
+    dict: bool,
+    // Generate __reduce__ so the owned and reference classes pickle as the owned value, implies dict
+    // The owned class needs #[pyclass(module = "...")] after the derive
+    #[darling(default)]
+    pickle: bool,
+}
+
+/// Parse the struct level #[py_bevy(...)] options
+pub(crate) fn parse_struct_attrs(
+    ast: &syn::DeriveInput,
+) -> Result<PyRefStructAttrs, proc_macro2::TokenStream> {
+    PyRefStructAttrs::from_derive_input(ast).map_err(|e| e.write_errors())
+}
+
+/// Python name of a generated reference class, ex: FooBevyRef or {class_name}BevyRef
+pub(crate) fn py_ref_class_name(
+    attrs: &PyRefStructAttrs,
+    ast: &syn::DeriveInput,
+    suffix: &str,
+) -> String {
+    match &attrs.class_name {
+        Some(class_name) => format!("{}{}", class_name, suffix),
+        None => format!("{}{}", ast.ident, suffix),
+    }
+}
+
+/// pyo3_stub_gen attribute to put on a generated reference class
+///
+/// Only generated with the gen-ref-stubs feature
+pub(crate) fn gen_stub_pyclass_attr(attrs: &PyRefStructAttrs) -> proc_macro2::TokenStream {
+    #[cfg(feature = "gen-ref-stubs")]
+    {
+        match &attrs.stub_gen_module {
+            Some(module) => quote! { #[pyo3_stub_gen::derive::gen_stub_pyclass(module = #module)] },
+            None => quote! { #[pyo3_stub_gen::derive::gen_stub_pyclass] },
+        }
+    }
+    #[cfg(not(feature = "gen-ref-stubs"))]
+    {
+        let _ = attrs;
+        quote! {}
+    }
+}
+
+/// Submit a typing.overload stub for every method in #[py_bevy(ref_overloads(...))]
+///
+/// ```
+/// #[py_bevy(ref_overloads(PyEntity::get))]
+/// struct Foo
+/// > class PyEntity:
+/// >     @typing.overload
+/// >     def get(self, ty: builtins.type[Foo], /) -> FooBevyRef: ...
+/// ```
+///
+/// Only generated with the gen-ref-stubs feature
+pub(crate) fn gen_ref_overload_stubs(
+    attrs: &PyRefStructAttrs,
+    ast: &syn::DeriveInput,
+    py_ref_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    #[cfg(feature = "gen-ref-stubs")]
+    {
+        let struct_name = &ast.ident;
+
+        let mut overload_stubs = Vec::new();
+        for method_path in attrs.ref_overloads.iter() {
+            let mut api_path = method_path.clone();
+            let method_name = match api_path.segments.pop() {
+                Some(method) if !api_path.segments.is_empty() => {
+                    method.into_value().ident.to_string()
+                }
+                _ => {
+                    overload_stubs.push(
+                        syn::Error::new_spanned(
+                            method_path,
+                            "expected a method path, ex: PyEntity::get",
+                        )
+                        .to_compile_error(),
+                    );
+                    continue;
+                }
+            };
+            // drop the trailing ::
+            let api_path = {
+                let leading_colon = &api_path.leading_colon;
+                let segments = api_path.segments.iter();
+                quote! { #leading_colon #(#segments)::* }
+            };
+
+            overload_stubs.push(quote! {
+                pyo3_stub_gen::inventory::submit! {
+                    pyo3_stub_gen::type_info::PyMethodsInfo {
+                        struct_id: std::any::TypeId::of::<#api_path>,
+                        attrs: &[],
+                        getters: &[],
+                        setters: &[],
+                        methods: &[
+                            pyo3_stub_gen::type_info::MethodInfo {
+                                name: #method_name,
+                                parameters: &[
+                                    pyo3_stub_gen::type_info::ParameterInfo {
+                                        name: "ty",
+                                        kind: pyo3_stub_gen::type_info::ParameterKind::PositionalOnly,
+                                        type_info: simple_py_bevy::stub_type_of::<#struct_name>,
+                                        default: pyo3_stub_gen::type_info::ParameterDefault::None,
+                                    },
+                                ],
+                                r#return: <#py_ref_name as pyo3_stub_gen::PyStubType>::type_output,
+                                doc: "",
+                                r#type: pyo3_stub_gen::type_info::MethodType::Instance,
+                                is_async: false,
+                                deprecated: None,
+                                type_ignored: None,
+                                is_overload: true,
+                            },
+                        ],
+                        file: file!(),
+                        line: line!(),
+                        column: column!(),
+                    }
+                }
+            });
+        }
+
+        quote! {
+            #(#overload_stubs)*
+        }
+    }
+    #[cfg(not(feature = "gen-ref-stubs"))]
+    {
+        let _ = (attrs, ast, py_ref_name);
+        quote! {}
+    }
+}
+
+/// pyo3_stub_gen attribute to put on the #[pymethods] of a generated reference class
+///
+/// Only generated with the gen-ref-stubs feature
+pub(crate) fn gen_stub_pymethods_attr() -> proc_macro2::TokenStream {
+    #[cfg(feature = "gen-ref-stubs")]
+    {
+        quote! { #[pyo3_stub_gen::derive::gen_stub_pymethods] }
+    }
+    #[cfg(not(feature = "gen-ref-stubs"))]
+    {
+        quote! {}
+    }
+}
+
+/// Auto generate the python dunder methods requested on the struct, ex: #[py_bevy(repr, eq)]
+pub(crate) fn gen_dunder_methods(
+    attrs: &PyRefStructAttrs,
+    ast: &syn::DeriveInput,
+    py_ref_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let struct_name = &ast.ident;
+    let mut dunder_fns = Vec::new();
+
+    if attrs.repr {
+        dunder_fns.push(quote! {
+            fn __repr__(&self) -> pyo3::PyResult<String> {
+                Ok(format!("{:?}", self.get_inner_ref()?))
+            }
+        });
+    }
+    if attrs.eq {
+        // pyo3 returns NotImplemented if other can't be extracted
+        dunder_fns.push(quote! {
+            fn __eq__(
+                &self,
+                other: simple_py_bevy::OwnedOrRef<#struct_name, #py_ref_name>,
+            ) -> pyo3::PyResult<bool> {
+                Ok(*self.get_inner_ref()? == other.0)
+            }
+        });
+    }
+    if attrs.hash {
+        dunder_fns.push(quote! {
+            fn __hash__(&self) -> pyo3::PyResult<u64> {
+                use std::hash::{Hash, Hasher};
+
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                self.get_inner_ref()?.hash(&mut hasher);
+                Ok(hasher.finish())
+            }
+        });
+    }
+    if attrs.copy {
+        dunder_fns.push(quote! {
+            fn __copy__(&self) -> pyo3::PyResult<#struct_name> {
+                Ok(self.get_inner_ref()?.clone())
+            }
+            fn __deepcopy__(
+                &self,
+                _memo: &pyo3::prelude::Bound<'_, pyo3::prelude::PyAny>,
+            ) -> pyo3::PyResult<#struct_name> {
+                Ok(self.get_inner_ref()?.clone())
+            }
+        });
+    }
+
+    quote! {
+        #(#dunder_fns)*
+    }
+}
+
 #[derive(Debug, FromField)]
 #[darling(attributes(py_bevy))]
 struct PyRefFieldAttrs {
@@ -158,15 +608,33 @@ struct PyRefFieldAttrs {
     skip: bool,
     #[darling(default)]
     get_only: bool,
+    // Python name of the getter and setter, defaults to the field name
+    #[darling(default)]
+    name: Option<String>,
+    // fn(&FieldType) -> PyResult<()> run on the new value before the setter writes it
+    #[darling(default)]
+    validate: Option<syn::Path>,
+    // Get a zero copy buffer of the field (AsBufferSlice) for numpy.asarray, read only with get_only
+    #[darling(default)]
+    numpy: bool,
+    // Include the field (ColumnField) in batch column reads and writes of a component
+    #[darling(default)]
+    #[cfg_attr(not(feature = "py-bevy"), allow(dead_code))]
+    column: bool,
 }
 
-fn transform_getter(attrs: &PyRefFieldAttrs, field: &syn::Field) -> proc_macro2::TokenStream {
+fn transform_getter(
+    attrs: &PyRefFieldAttrs,
+    field: &syn::Field,
+    py_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
     if attrs.skip {
         return quote! {}.into();
     }
 
     let field_name = field.ident.as_ref().unwrap();
     let getter_name = format_ident!("get_{}", field_name);
+    let field_docs = backend::get_doc_attrs(&field.attrs);
 
     let inner_name: syn::ExprField = syn::parse_quote! {
         parent.#field_name
@@ -174,10 +642,53 @@ fn transform_getter(attrs: &PyRefFieldAttrs, field: &syn::Field) -> proc_macro2:
 
     let mut ret_val = field.ty.clone();
 
+    if attrs.numpy {
+        let stub_return_type = cfg!(feature = "gen-ref-stubs").then(|| {
+            quote! {
+                #[gen_stub(override_return_type(
+                    type_repr = "collections.abc.Buffer",
+                    imports = ("collections.abc")
+                ))]
+            }
+        });
+        // the view finds the field again on every export, so it errors once the struct is gone.
+        // Arrays already made still point at the field, and python writes don't trigger change detection
+        let field_type = &field.ty;
+        let element = quote! { <#field_type as simple_py_bevy::AsBufferSlice>::Element };
+        let resolve = if attrs.get_only {
+            quote! {
+                let parent = this.get_inner_ref()?;
+                let data: *const [#element] = simple_py_bevy::AsBufferSlice::as_buffer_slice(&#inner_name);
+                Ok(data.cast_mut())
+            }
+        } else {
+            quote! {
+                this.map_to_inner_untracked(|parent| {
+                    simple_py_bevy::AsBufferSlice::as_buffer_slice_mut(&mut #inner_name) as *mut [#element]
+                })
+            }
+        };
+        let readonly = attrs.get_only;
+        return quote! {
+            #(#field_docs)*
+            #stub_return_type
+            #[getter(#py_name)]
+            fn #getter_name(slf: &pyo3::Bound<'_, Self>) -> pyo3::PyResult<simple_py_bevy::BufferView> {
+                let this = slf.clone().unbind();
+                let resolve = move |py: pyo3::Python<'_>| {
+                    let this = this.bind(py).try_borrow()?;
+                    #resolve
+                };
+                unsafe { simple_py_bevy::BufferView::from_field(resolve, #readonly, slf.py()) }
+            }
+        };
+    }
+
     if let Some(transform_ref_class) = &attrs.get_ref {
         ret_val = syn::Type::Path(transform_ref_class.clone());
         quote! {
-            #[getter]
+            #(#field_docs)*
+            #[getter(#py_name)]
             fn #getter_name(&mut self) -> pyo3::PyResult<#ret_val> {
                 self.map_to_inner(|mut inner| {
                     unsafe {
@@ -191,7 +702,8 @@ fn transform_getter(attrs: &PyRefFieldAttrs, field: &syn::Field) -> proc_macro2:
         .into()
     } else {
         quote! {
-            #[getter]
+            #(#field_docs)*
+            #[getter(#py_name)]
             fn #getter_name(&mut self) -> pyo3::PyResult<#ret_val> {
                 self.map_to_inner(|mut inner| {
                     unsafe {
@@ -204,7 +716,58 @@ fn transform_getter(attrs: &PyRefFieldAttrs, field: &syn::Field) -> proc_macro2:
         .into()
     }
 }
-fn transform_setter(attrs: &PyRefFieldAttrs, field: &syn::Field) -> proc_macro2::TokenStream {
+/// Type a setter accepts from python and how `val` of that type becomes the field type
+fn setter_value_type(
+    attrs: &PyRefFieldAttrs,
+    field: &syn::Field,
+) -> (syn::Type, proc_macro2::TokenStream) {
+    let field_type = &field.ty;
+
+    // numpy fields may not be python types themselves (ex: glam vectors), so they're set from their elements
+    if attrs.numpy {
+        return (
+            syn::parse_quote! { Vec<<#field_type as simple_py_bevy::AsBufferSlice>::Element> },
+            quote! { simple_py_bevy::AsBufferSlice::from_buffer_elements(val)? },
+        );
+    }
+
+    match (&attrs.other_set_type, &attrs.get_ref) {
+        (Some(rhs_type), _) => (
+            syn::parse_quote! { either::Either<#field_type, #rhs_type> },
+            quote! { val.into() },
+        ),
+        // the getter hands out a reference, so in-place ops (my_comp.inner += other) set the reference back
+        (None, Some(ref_type)) => (
+            syn::parse_quote! { simple_py_bevy::OwnedOrRef<#field_type, #ref_type> },
+            quote! { val.0 },
+        ),
+        (None, None) => (field.ty.clone(), quote! { val.into() }),
+    }
+}
+
+/// Python name of a field, from `name`, then the struct's `rename_all`, then the field name
+fn field_py_name(
+    struct_attrs: &PyRefStructAttrs,
+    attrs: &PyRefFieldAttrs,
+    field: &syn::Field,
+    field_name: &syn::Ident,
+) -> syn::Result<syn::Ident> {
+    let py_name = match (&attrs.name, &struct_attrs.rename_all) {
+        (Some(name), _) => name.clone(),
+        (None, Some(rule)) => rule.apply(&field_name.to_string()),
+        (None, None) => field_name.to_string(),
+    };
+    syn::parse_str::<syn::Ident>(&py_name).map_err(|_| {
+        syn::Error::new_spanned(field, format!("{:?} is not a valid python name", py_name))
+    })
+}
+
+fn transform_setter(
+    attrs: &PyRefFieldAttrs,
+    struct_validate: Option<&syn::Path>,
+    field: &syn::Field,
+    py_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
     if attrs.skip || attrs.get_only {
         return quote! {}.into();
     }
@@ -216,22 +779,46 @@ fn transform_setter(attrs: &PyRefFieldAttrs, field: &syn::Field) -> proc_macro2:
         parent.#field_name
     };
 
-    let field_type = field.ty.clone();
+    let (field_type, set_val) = setter_value_type(attrs, field);
+
+    let field_validate = attrs.validate.as_ref().map(|validate| {
+        quote! { #validate(&val)?; }
+    });
 
-    let field_type = match &attrs.other_set_type {
-        Some(rhs_type) => {
-            syn::parse_quote! { either::Either<#field_type, #rhs_type> }
+    // Validate a copy of the struct so nothing is written (or marked changed) when it fails
+    let struct_validate = struct_validate.map(|validate| {
+        quote! {
+            let val = {
+                let mut candidate = self.get_inner_ref()?.clone();
+                candidate.#field_name = val;
+                #validate(&candidate)?;
+                candidate.#field_name
+            };
         }
-        None => field.ty.clone(),
-    };
+    });
+
+    // numpy arrays may still view the old value, which the write would free
+    let exported_check = attrs.numpy.then(|| {
+        quote! {
+            simple_py_bevy::ensure_not_exported(
+                simple_py_bevy::AsBufferSlice::as_buffer_slice(&self.get_inner_ref()?.#field_name),
+            )?;
+        }
+    });
+
+    let orig_field_type = &field.ty;
 
     quote! {
-        #[setter]
+        #[setter(#py_name)]
         fn #setter_name(&mut self, val: #field_type) -> pyo3::PyResult<()> {
+            let val: #orig_field_type = #set_val;
+            #field_validate
+            #struct_validate
+            #exported_check
             self.map_to_inner(|mut inner| {
                 unsafe {
                     let mut parent = inner.as_mut();
-                    #inner_name = val.into();
+                    #inner_name = val;
                     Ok(())
                 }
             })
@@ -242,6 +829,7 @@ fn transform_setter(attrs: &PyRefFieldAttrs, field: &syn::Field) -> proc_macro2:
 
 /// Auto generate pyo3 getters and setters for all fields in the struct
 pub(crate) fn gen_get_set_for_fields_mapped_to_inner(
+    struct_attrs: &PyRefStructAttrs,
     ast: &syn::DeriveInput,
 ) -> proc_macro2::TokenStream {
     let mut transformed_fns = Vec::new();
@@ -251,21 +839,369 @@ pub(crate) fn gen_get_set_for_fields_mapped_to_inner(
             let attrs =
                 PyRefFieldAttrs::from_field(field).expect("Failed to parse field attributes");
 
-            if field.ident.is_none() {
+            let Some(field_name) = &field.ident else {
                 // skip over tuple structs
                 continue;
+            };
+
+            let py_name = match field_py_name(struct_attrs, &attrs, field, field_name) {
+                Ok(py_name) => py_name,
+                Err(e) => {
+                    transformed_fns.push(e.to_compile_error());
+                    continue;
+                }
+            };
+
+            // writes through a writable array can't be checked
+            if attrs.numpy
+                && !attrs.get_only
+                && (attrs.validate.is_some() || struct_attrs.validate.is_some())
+            {
+                transformed_fns.push(
+                    syn::Error::new_spanned(
+                        field,
+                        "numpy fields can't be validated, numpy writes skip validate. Use get_only",
+                    )
+                    .to_compile_error(),
+                );
+                continue;
             }
 
-            let getter = transform_getter(&attrs, &field);
-            let setter = transform_setter(&attrs, &field);
+            let getter = transform_getter(&attrs, field, &py_name);
+            let setter = transform_setter(&attrs, struct_attrs.validate.as_ref(), field, &py_name);
 
             transformed_fns.push(getter);
             transformed_fns.push(setter);
         }
     }
 
+    for property in &struct_attrs.property {
+        let fn_name = &property
+            .segments
+            .last()
+            .expect("path has no segments")
+            .ident;
+        let getter_name = format_ident!("get_{}", fn_name);
+        let py_name = match &struct_attrs.rename_all {
+            Some(rule) => format_ident!("{}", rule.apply(&fn_name.to_string())),
+            None => fn_name.clone(),
+        };
+
+        transformed_fns.push(quote! {
+            #[getter(#py_name)]
+            fn #getter_name(&self, py: pyo3::Python<'_>) -> pyo3::PyResult<pyo3::Py<pyo3::PyAny>> {
+                pyo3::IntoPyObjectExt::into_py_any(#property(self.get_inner_ref()?), py)
+            }
+        });
+    }
+
     quote! {
         #(#transformed_fns)*
     }
-    .into()
+}
+
+// whether a #[pyclass(...)] the derive can see sets module = "..."
+fn pyclass_has_module(attrs: &[syn::Attribute]) -> bool {
+    let mut has_module = false;
+    for attr in attrs {
+        let is_pyclass = attr
+            .path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "pyclass");
+        if !is_pyclass || !matches!(attr.meta, syn::Meta::List(_)) {
+            continue;
+        }
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("module") {
+                has_module = true;
+            }
+            if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        });
+    }
+    has_module
+}
+
+/// Implement PyDictConvert and expose to_dict/from_dict on the owned and reference classes
+///
+/// Fields with get_ref are converted with their own PyDictConvert, so the nested struct also needs `dict`.
+/// Skipped fields are filled with `Default::default()` by from_dict, so their types need `Default`
+pub(crate) fn gen_dict_methods(
+    struct_attrs: &PyRefStructAttrs,
+    ast: &syn::DeriveInput,
+    py_ref_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    if !struct_attrs.dict && !struct_attrs.pickle {
+        return quote! {};
+    }
+
+    let struct_name = &ast.ident;
+    let stub_gen_attr = gen_stub_pymethods_attr();
+
+    let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(fields),
+        ..
+    }) = &ast.data
+    else {
+        return syn::Error::new_spanned(ast, "dict is only supported on structs with named fields")
+            .to_compile_error();
+    };
+    // pickle finds from_dict through the owned class, so the class needs a module it can be imported from
+    if struct_attrs.pickle && !pyclass_has_module(&ast.attrs) {
+        return syn::Error::new_spanned(
+            struct_name,
+            "pickle needs #[pyclass(module = \"...\")] after the derive so pickle can import the owned class",
+        )
+        .to_compile_error();
+    }
+
+    let mut to_items = Vec::new();
+    let mut from_fields = Vec::new();
+    let mut errors = Vec::new();
+
+    for field in &fields.named {
+        let attrs = PyRefFieldAttrs::from_field(field).expect("Failed to parse field attributes");
+        let field_name = field.ident.as_ref().unwrap();
+
+        if attrs.skip {
+            // from_dict can only restore skipped fields to their default
+            if struct_attrs.pickle {
+                errors.push(
+                    syn::Error::new_spanned(
+                        field,
+                        "pickle can't restore skipped fields, unpickling would reset them to their default",
+                    )
+                    .to_compile_error(),
+                );
+            }
+            from_fields.push(quote! { #field_name: Default::default() });
+            continue;
+        }
+
+        let key = match field_py_name(struct_attrs, &attrs, field, field_name) {
+            Ok(py_name) => py_name.to_string(),
+            Err(e) => {
+                errors.push(e.to_compile_error());
+                continue;
+            }
+        };
+
+        let to_val = match (&attrs.get_ref, attrs.numpy) {
+            (_, true) => quote! {
+                pyo3::IntoPyObjectExt::into_py_any(
+                    simple_py_bevy::AsBufferSlice::as_buffer_slice(&self.#field_name).to_vec(),
+                    py,
+                )?
+            },
+            (Some(_), false) => {
+                quote! { simple_py_bevy::PyDictConvert::to_py_dict(&self.#field_name, py)? }
+            }
+            (None, false) => {
+                quote! { pyo3::IntoPyObjectExt::into_py_any(self.#field_name.clone(), py)? }
+            }
+        };
+        to_items.push(quote! {
+            pyo3::types::PyDictMethods::set_item(&dict, #key, #to_val)?;
+        });
+
+        let (set_type, set_val) = setter_value_type(&attrs, field);
+        let field_type = &field.ty;
+        let extract = quote! {
+            let val: #set_type = pyo3::prelude::PyAnyMethods::extract(&value)?;
+            #set_val
+        };
+        // nested references accept a dict as well as an owned value or reference
+        let extract = match &attrs.get_ref {
+            Some(_) if !attrs.numpy => quote! {
+                match value.cast::<pyo3::types::PyDict>() {
+                    Ok(nested) => simple_py_bevy::PyDictConvert::from_py_dict(nested)?,
+                    Err(_) => { #extract },
+                }
+            },
+            _ => extract,
+        };
+        let field_validate = attrs.validate.as_ref().map(|validate| {
+            quote! { #validate(&val)?; }
+        });
+
+        from_fields.push(quote! {
+            #field_name: {
+                let value = pyo3::types::PyDictMethods::get_item(dict, #key)?
+                    .ok_or_else(|| pyo3::exceptions::PyKeyError::new_err(#key))?;
+                let val: #field_type = { #extract };
+                #field_validate
+                val
+            }
+        });
+    }
+
+    let struct_validate = struct_attrs.validate.as_ref().map(|validate| {
+        quote! { #validate(&val)?; }
+    });
+
+    let reduce = |inner: proc_macro2::TokenStream| {
+        struct_attrs.pickle.then(|| {
+            quote! {
+                fn __reduce__<'py>(
+                    &self,
+                    py: pyo3::Python<'py>,
+                ) -> pyo3::PyResult<(
+                    pyo3::Bound<'py, pyo3::PyAny>,
+                    (pyo3::Bound<'py, pyo3::types::PyDict>,),
+                )> {
+                    let from_dict = pyo3::prelude::PyAnyMethods::getattr(
+                        py.get_type::<#struct_name>().as_any(),
+                        "from_dict",
+                    )?;
+                    Ok((from_dict, (simple_py_bevy::PyDictConvert::to_py_dict(#inner, py)?,)))
+                }
+            }
+        })
+    };
+    let owned_reduce = reduce(quote! { self });
+    let ref_reduce = reduce(quote! { self.get_inner_ref()? });
+
+    quote! {
+        #(#errors)*
+
+        impl simple_py_bevy::PyDictConvert for #struct_name {
+            fn to_py_dict<'py>(
+                &self,
+                py: pyo3::Python<'py>,
+            ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+                let dict = pyo3::types::PyDict::new(py);
+                #(#to_items)*
+                Ok(dict)
+            }
+            fn from_py_dict(dict: &pyo3::Bound<'_, pyo3::types::PyDict>) -> pyo3::PyResult<Self> {
+                let val = Self {
+                    #(#from_fields),*
+                };
+                #struct_validate
+                Ok(val)
+            }
+        }
+
+        #stub_gen_attr
+        #[pyo3::pymethods]
+        impl #struct_name {
+            fn to_dict<'py>(
+                &self,
+                py: pyo3::Python<'py>,
+            ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+                simple_py_bevy::PyDictConvert::to_py_dict(self, py)
+            }
+            #[staticmethod]
+            fn from_dict(dict: &pyo3::Bound<'_, pyo3::types::PyDict>) -> pyo3::PyResult<#struct_name> {
+                simple_py_bevy::PyDictConvert::from_py_dict(dict)
+            }
+            #owned_reduce
+        }
+
+        #stub_gen_attr
+        #[pyo3::pymethods]
+        impl #py_ref_name {
+            fn to_dict<'py>(
+                &self,
+                py: pyo3::Python<'py>,
+            ) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyDict>> {
+                simple_py_bevy::PyDictConvert::to_py_dict(self.get_inner_ref()?, py)
+            }
+            #[staticmethod]
+            fn from_dict(dict: &pyo3::Bound<'_, pyo3::types::PyDict>) -> pyo3::PyResult<#struct_name> {
+                simple_py_bevy::PyDictConvert::from_py_dict(dict)
+            }
+            #ref_reduce
+        }
+    }
+}
+
+/// Implement BevyPyColumns for a component, mapping python field names to its column fields
+#[cfg(feature = "py-bevy")]
+pub(crate) fn gen_column_impls(
+    struct_attrs: &PyRefStructAttrs,
+    ast: &syn::DeriveInput,
+) -> proc_macro2::TokenStream {
+    let struct_name = &ast.ident;
+    let mut keys = Vec::new();
+    let mut field_names = Vec::new();
+    let mut validates = Vec::new();
+    let mut errors = Vec::new();
+
+    if let syn::Data::Struct(data) = &ast.data {
+        for field in &data.fields {
+            let attrs =
+                PyRefFieldAttrs::from_field(field).expect("Failed to parse field attributes");
+            let Some(field_name) = &field.ident else {
+                continue;
+            };
+            if !attrs.column {
+                continue;
+            }
+            // column writes only see one field at a time
+            if struct_attrs.validate.is_some() {
+                errors.push(
+                    syn::Error::new_spanned(
+                        field,
+                        "column fields can't be in a struct with validate, column writes skip it",
+                    )
+                    .to_compile_error(),
+                );
+                continue;
+            }
+            match field_py_name(struct_attrs, &attrs, field, field_name) {
+                Ok(py_name) => keys.push(py_name.to_string()),
+                Err(e) => {
+                    errors.push(e.to_compile_error());
+                    continue;
+                }
+            }
+            field_names.push(field_name);
+            validates.push(match &attrs.validate {
+                Some(validate) => quote! { Some(#validate) },
+                None => quote! { None },
+            });
+        }
+    }
+
+    quote! {
+        #(#errors)*
+
+        impl simple_py_bevy::BevyPyColumns for #struct_name {
+            const COLUMNS: &'static [&'static str] = &[#(#keys),*];
+
+            fn column_to_numpy<'py>(
+                py: pyo3::Python<'py>,
+                field: &str,
+                comps: &[&Self],
+            ) -> Option<pyo3::PyResult<pyo3::Bound<'py, pyo3::PyAny>>> {
+                #[allow(unreachable_code, unused_variables)]
+                match field {
+                    #(#keys => Some(simple_py_bevy::column_to_numpy(py, comps, |comp| &comp.#field_names)),)*
+                    _ => None,
+                }
+            }
+            fn column_to_arrow(field: &str, comps: &[&Self]) -> Option<simple_py_bevy::ArrowColumn> {
+                #[allow(unreachable_code, unused_variables)]
+                match field {
+                    #(#keys => Some(simple_py_bevy::column_to_arrow(comps, |comp| &comp.#field_names)),)*
+                    _ => None,
+                }
+            }
+            fn column_writer(
+                field: &str,
+                column: &pyo3::Bound<'_, pyo3::PyAny>,
+                rows: usize,
+            ) -> Option<pyo3::PyResult<simple_py_bevy::ColumnWriter<Self>>> {
+                #[allow(unreachable_code, unused_variables)]
+                match field {
+                    #(#keys => Some(simple_py_bevy::column_writer(column, rows, |comp| &mut comp.#field_names, #validates)),)*
+                    _ => None,
+                }
+            }
+        }
+    }
 }
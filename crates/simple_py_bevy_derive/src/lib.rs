@@ -10,6 +10,7 @@ use proc_macro::TokenStream;
 
 #[cfg(feature = "py-ref")]
 mod backend;
+mod config_schema;
 #[cfg(feature = "py-ref")]
 mod py_ref;
 
@@ -22,10 +23,12 @@ mod py_bevy_config;
 mod py_bevy_meth;
 #[cfg(feature = "py-bevy")]
 mod py_bevy_res;
+mod rename_rule;
 mod simple_wrappers;
 
 /// Auto generate a BevyRef and a Ref version of this struct and add traits to load this object from yaml
-// todo: need to put yaml impl into a derive macro and remove this in favor of explicit derives
+///
+/// Loaders return a `simple_py_bevy::ConfigError` instead of panicking, see the README's Configs section for the options
 #[proc_macro_attribute]
 pub fn py_bevy_config_res(attr: TokenStream, input: TokenStream) -> TokenStream {
     let item = syn::parse(input).unwrap();
@@ -39,6 +42,33 @@ pub fn py_bevy_config_res(attr: TokenStream, input: TokenStream) -> TokenStream
     }
 }
 
+/// Implement MakePathsAbsolute by joining the `#[paths]` fields onto the config file's directory
+/// and make_paths_relative by stripping it again before saving
+///
+/// `#[paths]` works on `PathBuf`, `Option`, `Vec` and `Box` of them, and on nested types implementing MakePathsAbsolute.
+/// It's `#[paths]` and not `#[path]` because `#[path]` is rust's builtin attribute for module files
+#[proc_macro_derive(MakePathsAbsolute, attributes(paths))]
+pub fn derive_make_paths_absolute(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    match py_bevy_config::make_paths_absolute_derive_impl(&ast) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Implement ConfigSchema, a JSON Schema following the struct's serde attributes and `///` docs
+///
+/// Works on structs with named fields, newtypes and enums with only unit variants,
+/// fields need to implement ConfigSchema and `#[serde(default)]` fields need `Serialize`
+#[proc_macro_derive(ConfigSchema)]
+pub fn derive_config_schema(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    match config_schema::config_schema_impl(&ast) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 /// Simple wrapper macro to make creating a pyclass easier with auto stubs
 /// Handles pyo3 feature
 #[proc_macro_attribute]
@@ -63,6 +93,10 @@ pub fn simple_pymethods(attr: TokenStream, input: TokenStream) -> TokenStream {
 }
 
 /// Auto generate methods to expose this struct's methods to python
+///
+/// Arguments taking this struct by value (including numeric dunders like `__add__` and `__iadd__`) also accept its reference classes
+///
+/// Use `#[py_bevy(skip)]` on a method to leave it off of the generated BevyRef
 #[proc_macro_attribute]
 pub fn py_bevy_methods(_attr: TokenStream, _input: TokenStream) -> TokenStream {
     #[cfg(feature = "py-bevy")]
@@ -87,6 +121,8 @@ pub fn py_ref_methods(_attr: TokenStream, _input: TokenStream) -> TokenStream {
 }
 
 /// Generate a BevyCompRef version of this struct
+///
+/// Options are set with `#[py_bevy(...)]` on the struct and its fields, see the README for the full list
 #[proc_macro_derive(PyBevyCompRef, attributes(py_bevy))]
 pub fn derive_py_bevy_comp_structs(_input: TokenStream) -> TokenStream {
     #[cfg(feature = "py-bevy")]
@@ -108,6 +144,8 @@ pub fn derive_py_bevy_comp_structs(_input: TokenStream) -> TokenStream {
 }
 
 /// Generate a BevyResRef version of this struct
+///
+/// Options are set with `#[py_bevy(...)]` on the struct and its fields, see the README for the full list
 #[proc_macro_derive(PyBevyResRef, attributes(py_bevy))]
 pub fn derive_py_bevy_res_structs(_input: TokenStream) -> TokenStream {
     #[cfg(feature = "py-bevy")]
@@ -129,6 +167,8 @@ pub fn derive_py_bevy_res_structs(_input: TokenStream) -> TokenStream {
 }
 
 /// Generate a Ref version of this struct
+///
+/// Options are set with `#[py_bevy(...)]` on the struct and its fields, see the README for the full list
 #[proc_macro_derive(PyStructRef, attributes(py_bevy))]
 pub fn derive_py_ref_struct(_input: TokenStream) -> TokenStream {
     #[cfg(feature = "py-ref")]
@@ -207,6 +247,17 @@ mod dummy_pyo3 {
     }
 
     pub fn passthrough(input: TokenStream) -> TokenStream {
-        input
+        let mut ast = syn::parse_macro_input!(input as syn::ItemImpl);
+
+        // method level #[py_bevy] options aren't real attributes, so they need to be removed
+        for item in &mut ast.items {
+            if let syn::ImplItem::Fn(method) = item {
+                method.attrs.retain(|attr| !attr.path().is_ident("py_bevy"));
+            }
+        }
+        quote::quote! {
+            #ast
+        }
+        .into()
     }
 }
@@ -1,6 +1,6 @@
 extern crate proc_macro;
 extern crate quote;
-use crate::backend::BEVY_WORLD_PTR_DELETED_ERROR_MSG;
+use crate::backend::{self, BEVY_WORLD_PTR_DELETED_ERROR_MSG};
 use crate::expand_methods;
 use quote::quote;
 
@@ -8,14 +8,32 @@ pub(crate) fn derive_py_bevy_comp_struct_impl(ast: &syn::DeriveInput) -> proc_ma
     let struct_name = ast.ident.clone();
     let py_bevy_ref_name = quote::format_ident!("{}BevyRef", ast.ident);
 
-    let py_ref_get_set_fns = expand_methods::gen_get_set_for_fields_mapped_to_inner(&ast);
+    let struct_attrs = match expand_methods::parse_struct_attrs(ast) {
+        Ok(attrs) => attrs,
+        Err(e) => return e,
+    };
+    let py_class_name = expand_methods::py_ref_class_name(&struct_attrs, ast, "BevyRef");
+
+    let py_ref_get_set_fns =
+        expand_methods::gen_get_set_for_fields_mapped_to_inner(&struct_attrs, ast);
+    let py_ref_dunder_fns =
+        expand_methods::gen_dunder_methods(&struct_attrs, ast, &py_bevy_ref_name);
+    let struct_docs = backend::get_doc_attrs(&ast.attrs);
+    let stub_gen_attr = expand_methods::gen_stub_pyclass_attr(&struct_attrs);
+    let stub_gen_methods_attr = expand_methods::gen_stub_pymethods_attr();
+    let ref_overload_stubs =
+        expand_methods::gen_ref_overload_stubs(&struct_attrs, ast, &py_bevy_ref_name);
+    let dict_methods = expand_methods::gen_dict_methods(&struct_attrs, ast, &py_bevy_ref_name);
+    let column_impls = expand_methods::gen_column_impls(&struct_attrs, ast);
 
     // generate a hash function on the original struct to make lookup easier
     let hash_py_fn_export = expand_methods::export_hash_py_fn(&ast.ident);
     let downcast_reflect_export = expand_methods::export_downcast_into_py_any(&ast.ident);
 
     quote!(
-        #[pyo3::pyclass(unsendable)]
+        #(#struct_docs)*
+        #stub_gen_attr
+        #[pyo3::pyclass(unsendable, name = #py_class_name)]
         pub struct #py_bevy_ref_name {
             world: simple_py_bevy::UnsafeWorldRef,
             entity: simple_py_bevy::Entity,
@@ -53,6 +71,12 @@ pub(crate) fn derive_py_bevy_comp_struct_impl(ast: &syn::DeriveInput) -> proc_ma
             pub fn get_inner_ref_mut(&self) -> pyo3::prelude::PyResult<simple_py_bevy::Mut<'_, #struct_name>> {
                 self.world.get_comp_mut::<#struct_name>(&self.entity)
             }
+            // mutable access that doesn't mark it changed, for buffers python writes into later
+            #[allow(dead_code)]
+            fn map_to_inner_untracked<U>(&self, f: impl FnOnce(&mut #struct_name) -> U) -> pyo3::prelude::PyResult<U> {
+                let mut inner = self.world.get_comp_mut::<#struct_name>(&self.entity)?;
+                Ok(f(simple_py_bevy::DetectChangesMut::bypass_change_detection(&mut inner)))
+            }
 
             fn map_to_inner<'a, F, U>(&self, f: F) -> pyo3::PyResult<U>
             where
@@ -73,10 +97,17 @@ pub(crate) fn derive_py_bevy_comp_struct_impl(ast: &syn::DeriveInput) -> proc_ma
 
         #downcast_reflect_export
 
+        #ref_overload_stubs
+        #dict_methods
+        #column_impls
+
+
+        #stub_gen_methods_attr
         #[pyo3::pymethods]
         impl #py_bevy_ref_name {
             #py_ref_get_set_fns
-        
+            #py_ref_dunder_fns
+
             fn to_owned(&self) -> pyo3::prelude::PyResult<#struct_name> {
                 Ok(self.get_inner_ref()?.clone())
             }
@@ -10,50 +10,367 @@ struct ConfigStructArgs {
     #[darling(default)]
     name: Option<String>,
     yaml_env_var: syn::Path,
+    // expose the loaders and to_yaml to python, the struct must be a pyclass
+    #[darling(default)]
+    py_methods: bool,
+    // accepted file formats, ex: formats(yaml, toml), the first is used for unknown extensions
+    #[darling(default)]
+    formats: darling::util::PathList,
+    // generate from_layers, merging Default, files and env vars named {env_prefix}__FIELD__NESTED
+    #[darling(default)]
+    env_prefix: Option<String>,
+    // implement ReloadableConfig for ConfigHotReloadPlugin, the struct must be a Resource and Serialize
+    #[darling(default)]
+    hot_reload: bool,
+    // generate save and to_yaml_path, the struct must be Clone and Serialize
+    #[darling(default)]
+    save: bool,
+    // derive ConfigSchema, add json_schema and check files against it, collecting every error
+    #[darling(default)]
+    schema: bool,
+    // pieces the attribute shouldn't add, any of pyclass, bevy_ref, resource, serde
+    #[darling(default)]
+    skip: darling::util::PathList,
+}
+
+// Last path segments of the struct's pyclass attributes and derives, ex: Resource for bevy::prelude::Resource
+fn existing_attrs(attrs: &[syn::Attribute]) -> syn::Result<Vec<String>> {
+    let last_segment = |path: &syn::Path| {
+        path.segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default()
+    };
+    let mut existing = Vec::new();
+    for attr in attrs {
+        if attr.path().is_ident("derive") {
+            let derives = attr.parse_args_with(
+                syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+            )?;
+            existing.extend(derives.iter().map(last_segment));
+        } else {
+            existing.push(last_segment(attr.path()));
+        }
+    }
+    Ok(existing)
+}
+
+// #[simple_pyclass] and the derives composed onto the config struct, minus the skipped pieces
+//
+// Pieces already written below #[py_bevy_config_res] are left off, attributes above it have
+// already been expanded so they can't be seen and need skip(...)
+fn composed_attrs(
+    args: &ConfigStructArgs,
+    new_name: &str,
+    attrs: &[syn::Attribute],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut skipped = Vec::new();
+    for piece in args.skip.iter() {
+        match piece.get_ident().map(|i| i.to_string()).as_deref() {
+            Some(name @ ("pyclass" | "bevy_ref" | "resource" | "serde")) => {
+                skipped.push(name.to_string())
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    piece,
+                    "Expected one of pyclass, bevy_ref, resource, serde",
+                ))
+            }
+        }
+    }
+    let skips = |piece: &str| skipped.iter().any(|s| s == piece);
+    let existing = existing_attrs(attrs)?;
+    let exists = |name: &str| existing.iter().any(|e| e == name);
+
+    let has_pyclass = exists("pyclass") || exists("simple_pyclass") || exists("gen_stub_pyclass");
+    let pyclass = match skips("pyclass") || has_pyclass {
+        true => quote::quote! {},
+        false => quote::quote! { #[simple_py_bevy::simple_pyclass(name = #new_name)] },
+    };
+
+    let mut derives = Vec::new();
+    if !skips("serde") {
+        if !exists("Deserialize") {
+            derives.push(quote::quote!(serde::Deserialize));
+        }
+        if !exists("Serialize") {
+            derives.push(quote::quote!(serde::Serialize));
+        }
+    }
+    if !skips("resource") && !exists("Resource") {
+        derives.push(quote::quote!(bevy::prelude::Resource));
+    }
+    if !skips("bevy_ref") && !exists("PyBevyResRef") {
+        derives.push(quote::quote!(simple_py_bevy::PyBevyResRef));
+    }
+    let derives = match derives.is_empty() {
+        true => quote::quote! {},
+        false => quote::quote! { #[derive(#(#derives),*)] },
+    };
+
+    Ok(quote::quote! {
+        #pyclass
+        #derives
+    })
+}
+
+fn config_formats(args: &ConfigStructArgs) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    if args.formats.is_empty() {
+        return Ok(vec![quote::quote!(simple_py_bevy::ConfigFormat::Yaml)]);
+    }
+    args.formats
+        .iter()
+        .map(|format| {
+            let variant = match format.get_ident().map(|i| i.to_string()).as_deref() {
+                Some("yaml") => quote::quote!(Yaml),
+                Some("toml") => quote::quote!(Toml),
+                Some("json") => quote::quote!(Json),
+                Some("ron") => quote::quote!(Ron),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        format,
+                        "Expected one of yaml, toml, json, ron",
+                    ))
+                }
+            };
+            Ok(quote::quote!(simple_py_bevy::ConfigFormat::#variant))
+        })
+        .collect()
 }
 
 fn yaml_loader_impls(
     args: &ConfigStructArgs,
     new_name: &str,
     ast: &syn::ItemStruct,
-) -> proc_macro2::TokenStream {
+) -> syn::Result<proc_macro2::TokenStream> {
     let struct_name = &ast.ident;
 
     let yaml_env_var = &args.yaml_env_var;
     let mut yaml_env_ident = proc_macro2::TokenStream::new();
     yaml_env_var.to_tokens(&mut yaml_env_ident);
 
-    let info_str = format!(r#"Loaded {} yaml from >> {{:?}}"#, new_name);
+    let formats = config_formats(args)?;
+    let info_str = format!(r#"Loaded {} {{}} from >> {{:?}}"#, new_name);
 
-    quote::quote! {
-        impl #struct_name {
-            pub fn new_from_env_yaml_path() -> Result<Self, Box<dyn std::error::Error>> {
-                let var = std::env::var(#yaml_env_ident)?;
-                Ok(#struct_name::from_yaml(var.into()))
-            }
-
-            fn from_yaml(config_yaml_path: std::path::PathBuf) -> Self {
-                match std::fs::read_to_string(config_yaml_path.clone()) {
-                    Ok(yaml_str) => {
-                        let mut cfg = match serde_yaml::from_str::<Self>(&yaml_str)
-                        {
-                            Ok(cfg) => cfg,
-                            Err(what) => panic!("{}", what),
-                        };
-                        cfg.make_paths_absolute(&config_yaml_path.parent().unwrap().to_path_buf());
-
-                        bevy::log::info!(
-                            #info_str,
-                            config_yaml_path.clone()
-                        );
-                        return cfg;
+    // hot reloaded configs record every file they read, for ConfigHotReloadPlugin to watch
+    let record_source = |load: proc_macro2::TokenStream, paths, reload| match args.hot_reload {
+        true => quote::quote! {
+            let (cfg, files) = simple_py_bevy::track_config_files(|| #load);
+            if cfg.is_ok() {
+                simple_py_bevy::record_config_source(simple_py_bevy::ConfigSource::<Self>::loaded(
+                    #paths,
+                    files,
+                    #reload,
+                ));
+            }
+            cfg
+        },
+        false => load,
+    };
+
+    let layered_load = record_source(
+        quote::quote! { #struct_name::config_layers(&paths)?.build() },
+        quote::quote! { paths },
+        quote::quote! { |paths| #struct_name::from_layers(paths) },
+    );
+    let layered_loader = match &args.env_prefix {
+        Some(env_prefix) => quote::quote! {
+            /// Merge Default, then each file in order, then env vars prefixed with the config's env_prefix
+            pub fn from_layers<P: AsRef<std::path::Path>>(
+                paths: impl IntoIterator<Item = P>,
+            ) -> Result<Self, simple_py_bevy::ConfigError> {
+                let paths: Vec<std::path::PathBuf> = paths
+                    .into_iter()
+                    .map(|path| path.as_ref().to_path_buf())
+                    .collect();
+                #layered_load
+            }
+
+            /// The layers from_layers builds, more can be set on top before building
+            pub fn config_layers<P: AsRef<std::path::Path>>(
+                paths: impl IntoIterator<Item = P>,
+            ) -> Result<simple_py_bevy::ConfigLayers<Self>, simple_py_bevy::ConfigError> {
+                let mut layers = simple_py_bevy::ConfigLayers::new()?;
+                for path in paths {
+                    layers = layers.file(path, Self::CONFIG_FORMATS)?;
+                }
+                Ok(layers.env(#env_prefix))
+            }
+        },
+        None => quote::quote! {},
+    };
+
+    let reloadable = match args.hot_reload {
+        true => quote::quote! {
+            impl simple_py_bevy::ReloadableConfig for #struct_name {
+                fn env_config_path() -> Result<std::path::PathBuf, simple_py_bevy::ConfigError> {
+                    std::env::var(#yaml_env_ident)
+                        .map(std::path::PathBuf::from)
+                        .map_err(|source| simple_py_bevy::ConfigError::Env {
+                            var: #yaml_env_ident.to_string(),
+                            source,
+                        })
+                }
+                fn load_config_path(
+                    path: &std::path::Path,
+                ) -> Result<Self, simple_py_bevy::ConfigError> {
+                    #struct_name::from_config_path(path)
+                }
+            }
+        },
+        false => quote::quote! {},
+    };
+
+    let saver = match args.save {
+        true => quote::quote! {
+            /// Save the config in the format of the file's extension, one of CONFIG_FORMATS
+            ///
+            /// With `relative_paths`, paths under the file's directory are written relative to it
+            pub fn save(
+                &self,
+                config_path: impl AsRef<std::path::Path>,
+                relative_paths: bool,
+            ) -> Result<(), simple_py_bevy::ConfigError> {
+                let config_path = config_path.as_ref();
+                let format = simple_py_bevy::ConfigFormat::detect(config_path, Self::CONFIG_FORMATS)?;
+                simple_py_bevy::save_config(self, config_path, format, relative_paths)
+            }
+
+            /// Save the config as yaml, with paths under the file's directory relative to it
+            pub fn to_yaml_path(
+                &self,
+                config_yaml_path: impl AsRef<std::path::Path>,
+            ) -> Result<(), simple_py_bevy::ConfigError> {
+                simple_py_bevy::save_config(
+                    self,
+                    config_yaml_path.as_ref(),
+                    simple_py_bevy::ConfigFormat::Yaml,
+                    true,
+                )
+            }
+        },
+        false => quote::quote! {},
+    };
+    // python's to_yaml only needs Serialize, so it comes with py_methods too
+    let to_yaml = match args.save || args.py_methods {
+        true => quote::quote! {
+            /// The config as a yaml string, paths are written as they are
+            pub fn to_yaml(&self) -> Result<String, simple_py_bevy::ConfigError> {
+                simple_py_bevy::ConfigFormat::Yaml.serialize(std::path::Path::new(#new_name), self)
+            }
+        },
+        false => quote::quote! {},
+    };
+
+    let (schema_impl, schema_methods, load_config) = match args.schema {
+        true => {
+            let derive_input = syn::DeriveInput::from(ast.clone());
+            let schema_impl = crate::config_schema::config_schema_impl(&derive_input)?;
+            // writing the schema goes through serde_json
+            let write_json_schema = match cfg!(feature = "json") {
+                true => quote::quote! {
+                    /// Write json_schema to a file, for editors to complete and check configs with
+                    pub fn write_json_schema(
+                        schema_path: impl AsRef<std::path::Path>,
+                    ) -> Result<(), simple_py_bevy::ConfigError> {
+                        simple_py_bevy::write_config_schema(schema_path.as_ref(), &Self::json_schema())
                     }
-                    Err(what) => panic!(
-                        "Failed to read file {:?}: {}",
-                        config_yaml_path.clone(),
-                        what
-                    ),
+                },
+                false => quote::quote! {},
+            };
+            let schema_methods = quote::quote! {
+                /// JSON Schema of the config files, with field docs and defaults
+                pub fn json_schema() -> simple_py_bevy::ConfigValue {
+                    simple_py_bevy::root_config_schema::<Self>(#new_name)
                 }
+
+                #write_json_schema
+
+                /// Every mismatch between a config file and json_schema, empty when the file is valid
+                pub fn validate_config_path(
+                    config_path: impl AsRef<std::path::Path>,
+                ) -> Result<Vec<String>, simple_py_bevy::ConfigError> {
+                    let config_path = config_path.as_ref();
+                    let format = simple_py_bevy::ConfigFormat::detect(config_path, Self::CONFIG_FORMATS)?;
+                    simple_py_bevy::validate_config_path(config_path, format, &Self::json_schema())
+                }
+            };
+            let load_config = quote::quote! {
+                simple_py_bevy::load_config_checked(config_path, format, &Self::json_schema())?
+            };
+            (schema_impl, schema_methods, load_config)
+        }
+        false => (
+            quote::quote! {},
+            quote::quote! {},
+            quote::quote! { simple_py_bevy::load_config(config_path, format)? },
+        ),
+    };
+
+    let path_load = record_source(
+        quote::quote! { #struct_name::read_config_path(config_path, format) },
+        quote::quote! { vec![config_path.to_path_buf()] },
+        quote::quote! { |paths| #struct_name::from_config_path(&paths[0]) },
+    );
+
+    Ok(quote::quote! {
+        #reloadable
+        #schema_impl
+
+        impl #struct_name {
+            #layered_loader
+            #saver
+            #to_yaml
+            #schema_methods
+
+            /// File formats accepted by from_config_path
+            pub const CONFIG_FORMATS: &'static [simple_py_bevy::ConfigFormat] = &[#(#formats),*];
+
+            pub fn new_from_env_yaml_path() -> Result<Self, simple_py_bevy::ConfigError> {
+                let var = std::env::var(#yaml_env_ident).map_err(|source| {
+                    simple_py_bevy::ConfigError::Env {
+                        var: #yaml_env_ident.to_string(),
+                        source,
+                    }
+                })?;
+                #struct_name::from_config_path(var)
+            }
+
+            /// Load the config from a yaml file, relative paths are made absolute from the file's directory
+            pub fn from_yaml_path(
+                config_yaml_path: impl AsRef<std::path::Path>,
+            ) -> Result<Self, simple_py_bevy::ConfigError> {
+                #struct_name::from_path_with_format(config_yaml_path, simple_py_bevy::ConfigFormat::Yaml)
+            }
+
+            /// Load the config from any of CONFIG_FORMATS, picked by the file's extension
+            pub fn from_config_path(
+                config_path: impl AsRef<std::path::Path>,
+            ) -> Result<Self, simple_py_bevy::ConfigError> {
+                let config_path = config_path.as_ref();
+                let format = simple_py_bevy::ConfigFormat::detect(config_path, Self::CONFIG_FORMATS)?;
+                #struct_name::from_path_with_format(config_path, format)
+            }
+
+            pub fn from_path_with_format(
+                config_path: impl AsRef<std::path::Path>,
+                format: simple_py_bevy::ConfigFormat,
+            ) -> Result<Self, simple_py_bevy::ConfigError> {
+                let config_path = config_path.as_ref();
+                let cfg = { #path_load }?;
+
+                bevy::log::info!(#info_str, format, config_path);
+                Ok(cfg)
+            }
+
+            fn read_config_path(
+                config_path: &std::path::Path,
+                format: simple_py_bevy::ConfigFormat,
+            ) -> Result<Self, simple_py_bevy::ConfigError> {
+                let mut cfg: Self = #load_config;
+                let config_dir = config_path.parent().unwrap_or(std::path::Path::new(""));
+                cfg.make_paths_absolute(&config_dir.to_path_buf());
+                Ok(cfg)
             }
         }
 
@@ -62,12 +379,125 @@ fn yaml_loader_impls(
                 if self.is_some() {
                     Ok(self.unwrap())
                 } else {
-                    #struct_name::new_from_env_yaml_path()
+                    Ok(#struct_name::new_from_env_yaml_path()?)
+                }
+            }
+        }
+    })
+}
+
+/// Python methods wrapping the loaders, from_env raises KeyError when the env var isn't set and load errors are raised as OSError/ValueError
+#[cfg(feature = "py-ref")]
+fn py_loader_methods(args: &ConfigStructArgs, ast: &syn::ItemStruct) -> proc_macro2::TokenStream {
+    if !args.py_methods {
+        return quote::quote! {};
+    }
+    let struct_name = &ast.ident;
+    let stub_gen_attr = crate::expand_methods::gen_stub_pymethods_attr();
+
+    // kwargs override fields after the env vars, nested fields are set with dicts or FIELD__NESTED keys
+    let layered_loader = match &args.env_prefix {
+        Some(_) => quote::quote! {
+            #[staticmethod]
+            #[pyo3(name = "from_layers", signature = (paths = Vec::new(), **overrides))]
+            fn py_from_layers(
+                paths: Vec<std::path::PathBuf>,
+                overrides: Option<&pyo3::Bound<'_, pyo3::types::PyDict>>,
+            ) -> pyo3::PyResult<#struct_name> {
+                let mut layers = #struct_name::config_layers(paths)?;
+                for (key, val) in overrides.into_iter().flatten() {
+                    let key: String = pyo3::prelude::PyAnyMethods::extract(&key)?;
+                    let keys: Vec<&str> = key.split("__").collect();
+                    layers = layers.set(&keys, simple_py_bevy::py_to_config_value(&val)?);
                 }
+                Ok(layers.build()?)
+            }
+        },
+        None => quote::quote! {},
+    };
+
+    let saver = match args.save {
+        true => quote::quote! {
+            #[pyo3(name = "save", signature = (path, relative_paths = true))]
+            fn py_save(&self, path: std::path::PathBuf, relative_paths: bool) -> pyo3::PyResult<()> {
+                Ok(self.save(path, relative_paths)?)
+            }
+            #[pyo3(name = "to_yaml_path")]
+            fn py_to_yaml_path(&self, path: std::path::PathBuf) -> pyo3::PyResult<()> {
+                Ok(self.to_yaml_path(path)?)
+            }
+        },
+        false => quote::quote! {},
+    };
+
+    let py_write_json_schema = match cfg!(feature = "json") {
+        true => quote::quote! {
+            #[staticmethod]
+            #[pyo3(name = "write_json_schema")]
+            fn py_write_json_schema(path: std::path::PathBuf) -> pyo3::PyResult<()> {
+                Ok(#struct_name::write_json_schema(path)?)
+            }
+        },
+        false => quote::quote! {},
+    };
+    let schema = match args.schema {
+        true => quote::quote! {
+            #[staticmethod]
+            #[pyo3(name = "json_schema")]
+            fn py_json_schema(py: pyo3::Python<'_>) -> pyo3::PyResult<pyo3::Py<pyo3::PyAny>> {
+                simple_py_bevy::config_value_to_py(py, &#struct_name::json_schema())
+            }
+            #py_write_json_schema
+            #[staticmethod]
+            #[pyo3(name = "validate_config_path")]
+            fn py_validate_config_path(path: std::path::PathBuf) -> pyo3::PyResult<Vec<String>> {
+                Ok(#struct_name::validate_config_path(path)?)
             }
+        },
+        false => quote::quote! {},
+    };
+
+    quote::quote! {
+        #stub_gen_attr
+        #[pyo3::pymethods]
+        impl #struct_name {
+            #[staticmethod]
+            #[pyo3(name = "from_yaml_path")]
+            fn py_from_yaml_path(path: std::path::PathBuf) -> pyo3::PyResult<#struct_name> {
+                Ok(#struct_name::from_yaml_path(path)?)
+            }
+            #[staticmethod]
+            #[pyo3(name = "from_config_path")]
+            fn py_from_config_path(path: std::path::PathBuf) -> pyo3::PyResult<#struct_name> {
+                Ok(#struct_name::from_config_path(path)?)
+            }
+            #[staticmethod]
+            #[pyo3(name = "from_yaml")]
+            fn py_from_yaml(path: std::path::PathBuf) -> pyo3::PyResult<#struct_name> {
+                Ok(#struct_name::from_yaml_path(path)?)
+            }
+            #[staticmethod]
+            #[pyo3(name = "from_env")]
+            fn py_from_env() -> pyo3::PyResult<#struct_name> {
+                Ok(#struct_name::new_from_env_yaml_path()?)
+            }
+            #[pyo3(name = "to_yaml")]
+            fn py_to_yaml(&self) -> pyo3::PyResult<String> {
+                Ok(self.to_yaml()?)
+            }
+            #layered_loader
+            #saver
+            #schema
         }
     }
-    .into()
+}
+
+#[cfg(not(feature = "py-ref"))]
+fn py_loader_methods(args: &ConfigStructArgs, ast: &syn::ItemStruct) -> proc_macro2::TokenStream {
+    if !args.py_methods {
+        return quote::quote! {};
+    }
+    syn::Error::new_spanned(&ast.ident, "py_methods needs the py-ref feature").into_compile_error()
 }
 
 #[allow(unused_variables)]
@@ -86,16 +516,70 @@ pub(crate) fn py_bevy_config_res_struct_impl(
         };
 
         let new_name = match &args.name {
-            Some(n) => format!(r#"{}"#, n),
-            None => format!(r#"{}"#, struct_name),
+            Some(n) => n.to_string(),
+            None => struct_name.to_string(),
         };
 
-        let yaml_impl_export = yaml_loader_impls(&args, &new_name, &ast);
+        let composed_attrs = match composed_attrs(&args, &new_name, &ast.attrs) {
+            Ok(v) => v,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        let yaml_impl_export = match yaml_loader_impls(&args, &new_name, &ast) {
+            Ok(v) => v,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        let py_loader_export = py_loader_methods(&args, &ast);
 
         quote::quote!(
+            #composed_attrs
             #ast
             #yaml_impl_export
+            #py_loader_export
         )
         .into()
     }
 }
+
+/// MakePathsAbsolute forwarded to every #[paths] field
+pub(crate) fn make_paths_absolute_derive_impl(
+    ast: &syn::DeriveInput,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let syn::Data::Struct(data) = &ast.data else {
+        return Err(syn::Error::new_spanned(
+            struct_name,
+            "#[derive(MakePathsAbsolute)] only supports structs",
+        ));
+    };
+
+    let mut members = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("paths"))
+        else {
+            continue;
+        };
+        attr.meta.require_path_only()?;
+
+        let member = match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(i.into()),
+        };
+        members.push(member);
+    }
+
+    Ok(quote::quote! {
+        impl #impl_generics simple_py_bevy::MakePathsAbsolute for #struct_name #ty_generics #where_clause {
+            fn make_paths_absolute(&mut self, parent_path: &std::path::PathBuf) {
+                #(simple_py_bevy::MakePathsAbsolute::make_paths_absolute(&mut self.#members, parent_path);)*
+            }
+            fn make_paths_relative(&mut self, parent_path: &std::path::PathBuf) {
+                #(simple_py_bevy::MakePathsAbsolute::make_paths_relative(&mut self.#members, parent_path);)*
+            }
+        }
+    })
+}
@@ -1,7 +1,7 @@
 extern crate proc_macro;
 extern crate quote;
 use crate::expand_methods;
-use crate::backend::BEVY_WORLD_PTR_DELETED_ERROR_MSG;
+use crate::backend::{self, BEVY_WORLD_PTR_DELETED_ERROR_MSG};
 
 /// Auto generate a struct with a reference to the original type
 /// Also generate pyo3 getters and setters for all members without the skip attribute
@@ -9,11 +9,25 @@ pub(crate) fn py_ref_struct_impl(ast: &syn::DeriveInput) -> proc_macro2::TokenSt
     let struct_name = ast.ident.clone();
     let py_ref_name = quote::format_ident!("{}Ref", ast.ident);
 
-    let py_ref_get_set_fns = expand_methods::gen_get_set_for_fields_mapped_to_inner(&ast);
+    let struct_attrs = match expand_methods::parse_struct_attrs(ast) {
+        Ok(attrs) => attrs,
+        Err(e) => return e,
+    };
+    let py_class_name = expand_methods::py_ref_class_name(&struct_attrs, ast, "Ref");
+
+    let py_ref_get_set_fns = expand_methods::gen_get_set_for_fields_mapped_to_inner(&struct_attrs, ast);
+    let py_ref_dunder_fns = expand_methods::gen_dunder_methods(&struct_attrs, ast, &py_ref_name);
+    let struct_docs = backend::get_doc_attrs(&ast.attrs);
+    let stub_gen_attr = expand_methods::gen_stub_pyclass_attr(&struct_attrs);
+    let stub_gen_methods_attr = expand_methods::gen_stub_pymethods_attr();
+    let ref_overload_stubs = expand_methods::gen_ref_overload_stubs(&struct_attrs, ast, &py_ref_name);
+    let dict_methods = expand_methods::gen_dict_methods(&struct_attrs, ast, &py_ref_name);
 
     quote::quote!(
         #[derive(Clone)]
-        #[pyo3::pyclass(unsendable)]
+        #(#struct_docs)*
+        #stub_gen_attr
+        #[pyo3::pyclass(unsendable, name = #py_class_name)]
         pub struct #py_ref_name {
             parent_ref: std::ptr::NonNull<#struct_name>,
             alive_ptr: std::sync::Weak<bool>
@@ -46,6 +60,10 @@ pub(crate) fn py_ref_struct_impl(ast: &syn::DeriveInput) -> proc_macro2::TokenSt
                     None => Err(pyo3::exceptions::PyValueError::new_err(#BEVY_WORLD_PTR_DELETED_ERROR_MSG)),
                 }
             }
+            #[allow(dead_code)]
+            fn map_to_inner_untracked<U>(&self, f: impl FnOnce(&mut #struct_name) -> U) -> pyo3::prelude::PyResult<U> {
+                Ok(f(self.get_inner_ref_mut()?))
+            }
         }
 
         impl std::ops::Deref for #py_ref_name {
@@ -85,9 +103,15 @@ pub(crate) fn py_ref_struct_impl(ast: &syn::DeriveInput) -> proc_macro2::TokenSt
             }
         }
 
+        #ref_overload_stubs
+        #dict_methods
+
+
+        #stub_gen_methods_attr
         #[pyo3::pymethods]
         impl #py_ref_name {
             #py_ref_get_set_fns
+            #py_ref_dunder_fns
 
             fn to_owned(&self) -> pyo3::prelude::PyResult<#struct_name> {
                 Ok(self.get_inner_ref()?.clone())
@@ -0,0 +1,268 @@
+/*
+    rename_all case conversions, shared by #[py_bevy(rename_all)] and #[serde(rename_all)]
+*/
+
+/// Case conversions for `rename_all = "..."`
+///
+/// `apply` mirrors pyo3's rename_all for python names,
+/// `apply_to_field` and `apply_to_variant` mirror serde's for config files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenameRule {
+    CamelCase,
+    PascalCase,
+    SnakeCase,
+    ScreamingSnakeCase,
+    KebabCase,
+    ScreamingKebabCase,
+    Lowercase,
+    Uppercase,
+}
+#[cfg(feature = "py-ref")]
+impl darling::FromMeta for RenameRule {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match Self::from_str(value) {
+            // python names can't have dashes
+            Some(Self::KebabCase | Self::ScreamingKebabCase) | None => {
+                Err(darling::Error::unknown_value(value))
+            }
+            Some(rule) => Ok(rule),
+        }
+    }
+}
+impl RenameRule {
+    /// Parse a rule with serde's names for it
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "camelCase" => Some(Self::CamelCase),
+            "PascalCase" => Some(Self::PascalCase),
+            "snake_case" => Some(Self::SnakeCase),
+            "SCREAMING_SNAKE_CASE" => Some(Self::ScreamingSnakeCase),
+            "kebab-case" => Some(Self::KebabCase),
+            "SCREAMING-KEBAB-CASE" => Some(Self::ScreamingKebabCase),
+            "lowercase" => Some(Self::Lowercase),
+            "UPPERCASE" => Some(Self::Uppercase),
+            _ => None,
+        }
+    }
+
+    /// Convert a snake_case rust name
+    ///
+    /// # Examples
+    /// ```
+    /// RenameRule::CamelCase.apply("foo_bar")
+    /// > fooBar
+    /// ```
+    #[cfg(feature = "py-ref")]
+    pub fn apply(&self, name: &str) -> String {
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        };
+        let words = name.split('_').filter(|word| !word.is_empty());
+
+        match self {
+            Self::CamelCase => words
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.to_string()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+            Self::PascalCase => words.map(capitalize).collect(),
+            Self::SnakeCase => name.to_string(),
+            Self::ScreamingSnakeCase => name.to_uppercase(),
+            Self::KebabCase => name.replace('_', "-"),
+            Self::ScreamingKebabCase => name.replace('_', "-").to_uppercase(),
+            Self::Lowercase => name.replace('_', ""),
+            Self::Uppercase => name.replace('_', "").to_uppercase(),
+        }
+    }
+
+    /// Rename a snake_case field like serde does, only underscores start new words
+    ///
+    /// # Examples
+    /// ```
+    /// RenameRule::PascalCase.apply_to_field("my_fieldA")
+    /// > MyFieldA
+    /// ```
+    pub fn apply_to_field(&self, field: &str) -> String {
+        match self {
+            Self::Lowercase | Self::SnakeCase => field.to_string(),
+            Self::Uppercase | Self::ScreamingSnakeCase => field.to_ascii_uppercase(),
+            Self::PascalCase => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            Self::CamelCase => {
+                let pascal = Self::PascalCase.apply_to_field(field);
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => pascal,
+                }
+            }
+            Self::KebabCase => field.replace('_', "-"),
+            Self::ScreamingKebabCase => field.to_ascii_uppercase().replace('_', "-"),
+        }
+    }
+
+    /// Rename a PascalCase variant like serde does, every capital starts a new word
+    ///
+    /// # Examples
+    /// ```
+    /// RenameRule::SnakeCase.apply_to_variant("HttpServer")
+    /// > http_server
+    /// ```
+    pub fn apply_to_variant(&self, variant: &str) -> String {
+        match self {
+            Self::PascalCase => variant.to_string(),
+            Self::Lowercase => variant.to_ascii_lowercase(),
+            Self::Uppercase => variant.to_ascii_uppercase(),
+            Self::CamelCase => {
+                let mut chars = variant.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            Self::SnakeCase => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            Self::ScreamingSnakeCase => Self::SnakeCase
+                .apply_to_variant(variant)
+                .to_ascii_uppercase(),
+            Self::KebabCase => Self::SnakeCase.apply_to_variant(variant).replace('_', "-"),
+            Self::ScreamingKebabCase => Self::ScreamingSnakeCase
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RenameRule;
+
+    #[test]
+    #[cfg(feature = "py-ref")]
+    fn rename_rule_cases() {
+        let name = "long_field_name";
+        assert_eq!(RenameRule::CamelCase.apply(name), "longFieldName");
+        assert_eq!(RenameRule::PascalCase.apply(name), "LongFieldName");
+        assert_eq!(RenameRule::SnakeCase.apply(name), "long_field_name");
+        assert_eq!(
+            RenameRule::ScreamingSnakeCase.apply(name),
+            "LONG_FIELD_NAME"
+        );
+        assert_eq!(RenameRule::Lowercase.apply(name), "longfieldname");
+        assert_eq!(RenameRule::Uppercase.apply(name), "LONGFIELDNAME");
+    }
+
+    #[test]
+    #[cfg(feature = "py-ref")]
+    fn rename_rule_digits_and_acronyms() {
+        assert_eq!(RenameRule::CamelCase.apply("pos_2d"), "pos2d");
+        assert_eq!(RenameRule::CamelCase.apply("field_1_name"), "field1Name");
+        assert_eq!(RenameRule::PascalCase.apply("http_url"), "HttpUrl");
+        // words keep their existing capitals
+        assert_eq!(RenameRule::CamelCase.apply("raw_HTTP_id"), "rawHTTPId");
+        assert_eq!(RenameRule::ScreamingSnakeCase.apply("vel_3"), "VEL_3");
+    }
+
+    #[test]
+    #[cfg(feature = "py-ref")]
+    fn rename_rule_skips_extra_underscores() {
+        assert_eq!(RenameRule::CamelCase.apply("_private__name"), "privateName");
+        assert_eq!(
+            RenameRule::SnakeCase.apply("_private__name"),
+            "_private__name"
+        );
+    }
+
+    #[test]
+    fn serde_field_rules() {
+        assert_eq!(
+            RenameRule::CamelCase.apply_to_field("layer_name"),
+            "layerName"
+        );
+        assert_eq!(
+            RenameRule::CamelCase.apply_to_field("max_2d_size"),
+            "max2dSize"
+        );
+        assert_eq!(
+            RenameRule::PascalCase.apply_to_field("layer2_name"),
+            "Layer2Name"
+        );
+        // capitals in fields don't start words and snake_case leaves fields as they are
+        assert_eq!(
+            RenameRule::PascalCase.apply_to_field("my_fieldA"),
+            "MyFieldA"
+        );
+        assert_eq!(
+            RenameRule::SnakeCase.apply_to_field("my_fieldA"),
+            "my_fieldA"
+        );
+        assert_eq!(
+            RenameRule::Lowercase.apply_to_field("layer_name"),
+            "layer_name"
+        );
+        assert_eq!(
+            RenameRule::ScreamingKebabCase.apply_to_field("layer_name"),
+            "LAYER-NAME"
+        );
+    }
+
+    #[test]
+    fn serde_variant_rules() {
+        assert_eq!(
+            RenameRule::CamelCase.apply_to_variant("HttpServer"),
+            "httpServer"
+        );
+        assert_eq!(RenameRule::CamelCase.apply_to_variant("UI"), "uI");
+        // every capital starts a word, acronyms included
+        assert_eq!(
+            RenameRule::SnakeCase.apply_to_variant("HTTPServer"),
+            "h_t_t_p_server"
+        );
+        assert_eq!(
+            RenameRule::ScreamingSnakeCase.apply_to_variant("Mode2D"),
+            "MODE2_D"
+        );
+        assert_eq!(
+            RenameRule::KebabCase.apply_to_variant("HttpServer"),
+            "http-server"
+        );
+        assert_eq!(
+            RenameRule::Lowercase.apply_to_variant("HttpServer"),
+            "httpserver"
+        );
+        assert_eq!(
+            RenameRule::PascalCase.apply_to_variant("HttpServer"),
+            "HttpServer"
+        );
+    }
+}
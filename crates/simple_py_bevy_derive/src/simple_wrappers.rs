@@ -46,7 +46,7 @@ pub(crate) fn export_to_owned_stubs(
     }
     #[cfg(not(feature = "gen-to-owned-stubs"))]
     {
-        quote! {}.into()
+        quote! {}
     }
 }
 
@@ -0,0 +1,489 @@
+use crate::ColumnField;
+use pyo3::{prelude::*, types::PyCapsule};
+use std::{
+    any::Any,
+    ffi::{c_char, c_void, CStr, CString},
+    sync::Arc,
+};
+
+/// Primitive types with an Arrow C data interface format
+///
+/// # Safety
+/// `ARROW_FORMAT` must be the arrow format string matching the layout of `Self`
+pub unsafe trait ArrowElement: Copy + Send + Sync + 'static {
+    const ARROW_FORMAT: &'static CStr;
+}
+
+macro_rules! impl_arrow_element {
+    ($($ty:ty => $format:literal),* $(,)?) => {
+        $(unsafe impl ArrowElement for $ty {
+            const ARROW_FORMAT: &'static CStr = $format;
+        })*
+    };
+}
+
+impl_arrow_element!(
+    i8 => c"c",
+    u8 => c"C",
+    i16 => c"s",
+    u16 => c"S",
+    i32 => c"i",
+    u32 => c"I",
+    i64 => c"l",
+    u64 => c"L",
+    f32 => c"f",
+    f64 => c"g",
+);
+
+/// A column of a record batch, 1 wide fields are primitive arrays and wider fields are fixed size lists
+pub struct ArrowColumn {
+    format: &'static CStr,
+    width: usize,
+    rows: usize,
+    ptr: *const c_void,
+    data: Arc<dyn Any + Send + Sync>,
+}
+
+impl ArrowColumn {
+    pub fn from_vec<T: ArrowElement>(data: Vec<T>, width: usize) -> Self {
+        let rows = data.len() / width.max(1);
+        Self {
+            format: T::ARROW_FORMAT,
+            width,
+            rows,
+            ptr: data.as_ptr() as *const c_void,
+            data: Arc::new(data),
+        }
+    }
+
+    fn export_schema(&self, name: &str) -> FFI_ArrowSchema {
+        match self.width {
+            1 => FFI_ArrowSchema::new(self.format, name, vec![]),
+            width => FFI_ArrowSchema::new(
+                &CString::new(format!("+w:{}", width)).unwrap(),
+                name,
+                vec![FFI_ArrowSchema::new(self.format, "item", vec![])],
+            ),
+        }
+    }
+
+    fn export_array(&self) -> FFI_ArrowArray {
+        let values_array = |length| {
+            FFI_ArrowArray::new(
+                length,
+                vec![std::ptr::null(), self.ptr],
+                Some(self.data.clone()),
+                vec![],
+            )
+        };
+
+        match self.width {
+            1 => values_array(self.rows),
+            width => FFI_ArrowArray::new(
+                self.rows,
+                vec![std::ptr::null()],
+                None,
+                vec![values_array(self.rows * width)],
+            ),
+        }
+    }
+}
+
+/// Pack a field of every component into an arrow column
+pub fn column_to_arrow<C, F>(comps: &[&C], get: fn(&C) -> &F) -> ArrowColumn
+where
+    F: ColumnField,
+    F::Element: ArrowElement,
+{
+    let mut data = vec![F::Element::default(); comps.len() * F::WIDTH];
+    for (comp, row) in comps.iter().zip(data.chunks_exact_mut(F::WIDTH)) {
+        get(comp).write_row(row);
+    }
+    ArrowColumn::from_vec(data, F::WIDTH)
+}
+
+/// Named columns with the same number of rows, exported to python through the arrow PyCapsule interface
+///
+/// Convert with `pyarrow.record_batch(batch)`, `polars.from_arrow(...)` of that, or any `__arrow_c_array__` consumer
+#[pyclass(unsendable)]
+pub struct ArrowRecordBatch {
+    rows: usize,
+    columns: Vec<(String, ArrowColumn)>,
+}
+
+impl ArrowRecordBatch {
+    pub fn new(rows: usize, columns: Vec<(String, ArrowColumn)>) -> PyResult<Self> {
+        if let Some((name, _)) = columns.iter().find(|(_, column)| column.rows != rows) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Column {} doesn't have {} rows",
+                name, rows
+            )));
+        }
+        Ok(Self { rows, columns })
+    }
+
+    fn export_schema(&self) -> FFI_ArrowSchema {
+        let schemas = self
+            .columns
+            .iter()
+            .map(|(name, column)| column.export_schema(name))
+            .collect();
+        FFI_ArrowSchema::new(c"+s", "", schemas)
+    }
+
+    fn export_array(&self) -> FFI_ArrowArray {
+        let arrays = self
+            .columns
+            .iter()
+            .map(|(_, column)| column.export_array())
+            .collect();
+        FFI_ArrowArray::new(self.rows, vec![std::ptr::null()], None, arrays)
+    }
+}
+
+#[pymethods]
+impl ArrowRecordBatch {
+    fn __len__(&self) -> usize {
+        self.rows
+    }
+
+    fn __arrow_c_schema__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyCapsule>> {
+        schema_capsule(py, self.export_schema())
+    }
+
+    // requested_schema is a hint, the batch is always exported as is
+    #[pyo3(signature = (requested_schema = None))]
+    fn __arrow_c_array__<'py>(
+        &self,
+        py: Python<'py>,
+        requested_schema: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<(Bound<'py, PyCapsule>, Bound<'py, PyCapsule>)> {
+        let _ = requested_schema;
+        Ok((
+            schema_capsule(py, self.export_schema())?,
+            array_capsule(py, self.export_array())?,
+        ))
+    }
+}
+
+// The consumer moves the struct out and sets release to null, otherwise the capsule releases it
+fn schema_capsule(py: Python<'_>, schema: FFI_ArrowSchema) -> PyResult<Bound<'_, PyCapsule>> {
+    PyCapsule::new_with_destructor(py, schema, Some(c"arrow_schema".into()), |mut schema, _| {
+        if let Some(release) = schema.release {
+            unsafe { release(&mut schema) }
+        }
+    })
+}
+
+fn array_capsule(py: Python<'_>, array: FFI_ArrowArray) -> PyResult<Bound<'_, PyCapsule>> {
+    PyCapsule::new_with_destructor(py, array, Some(c"arrow_array".into()), |mut array, _| {
+        if let Some(release) = array.release {
+            unsafe { release(&mut array) }
+        }
+    })
+}
+
+// Arrow C data interface, https://arrow.apache.org/docs/format/CDataInterface.html
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct FFI_ArrowSchema {
+    format: *const c_char,
+    name: *const c_char,
+    metadata: *const c_char,
+    flags: i64,
+    n_children: i64,
+    children: *mut *mut FFI_ArrowSchema,
+    dictionary: *mut FFI_ArrowSchema,
+    release: Option<unsafe extern "C" fn(*mut FFI_ArrowSchema)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct FFI_ArrowArray {
+    length: i64,
+    null_count: i64,
+    offset: i64,
+    n_buffers: i64,
+    n_children: i64,
+    buffers: *mut *const c_void,
+    children: *mut *mut FFI_ArrowArray,
+    dictionary: *mut FFI_ArrowArray,
+    release: Option<unsafe extern "C" fn(*mut FFI_ArrowArray)>,
+    private_data: *mut c_void,
+}
+
+// only touched by whoever holds the struct, and the data behind it is immutable
+unsafe impl Send for FFI_ArrowSchema {}
+unsafe impl Send for FFI_ArrowArray {}
+
+struct SchemaPrivate {
+    format: CString,
+    name: CString,
+    children: Box<[*mut FFI_ArrowSchema]>,
+}
+
+struct ArrayPrivate {
+    _data: Option<Arc<dyn Any + Send + Sync>>,
+    buffers: Box<[*const c_void]>,
+    children: Box<[*mut FFI_ArrowArray]>,
+}
+
+impl FFI_ArrowSchema {
+    fn new(format: &CStr, name: &str, children: Vec<FFI_ArrowSchema>) -> Self {
+        let children: Box<[_]> = children
+            .into_iter()
+            .map(|child| Box::into_raw(Box::new(child)))
+            .collect();
+        let mut private = Box::new(SchemaPrivate {
+            format: format.to_owned(),
+            name: CString::new(name).unwrap_or_default(),
+            children,
+        });
+
+        Self {
+            format: private.format.as_ptr(),
+            name: private.name.as_ptr(),
+            metadata: std::ptr::null(),
+            flags: 0,
+            n_children: private.children.len() as i64,
+            children: private.children.as_mut_ptr(),
+            dictionary: std::ptr::null_mut(),
+            release: Some(release_schema),
+            private_data: Box::into_raw(private) as *mut c_void,
+        }
+    }
+}
+
+impl FFI_ArrowArray {
+    fn new(
+        length: usize,
+        buffers: Vec<*const c_void>,
+        data: Option<Arc<dyn Any + Send + Sync>>,
+        children: Vec<FFI_ArrowArray>,
+    ) -> Self {
+        let children: Box<[_]> = children
+            .into_iter()
+            .map(|child| Box::into_raw(Box::new(child)))
+            .collect();
+        let mut private = Box::new(ArrayPrivate {
+            _data: data,
+            buffers: buffers.into_boxed_slice(),
+            children,
+        });
+
+        Self {
+            length: length as i64,
+            null_count: 0,
+            offset: 0,
+            n_buffers: private.buffers.len() as i64,
+            n_children: private.children.len() as i64,
+            buffers: private.buffers.as_mut_ptr(),
+            children: private.children.as_mut_ptr(),
+            dictionary: std::ptr::null_mut(),
+            release: Some(release_array),
+            private_data: Box::into_raw(private) as *mut c_void,
+        }
+    }
+}
+
+unsafe extern "C" fn release_schema(schema: *mut FFI_ArrowSchema) {
+    let Some(schema) = (unsafe { schema.as_mut() }) else {
+        return;
+    };
+    let private = unsafe { Box::from_raw(schema.private_data as *mut SchemaPrivate) };
+    for child in private.children.iter() {
+        let mut child = unsafe { Box::from_raw(*child) };
+        if let Some(release) = child.release {
+            unsafe { release(&mut *child) };
+        }
+    }
+    schema.release = None;
+}
+
+unsafe extern "C" fn release_array(array: *mut FFI_ArrowArray) {
+    let Some(array) = (unsafe { array.as_mut() }) else {
+        return;
+    };
+    let private = unsafe { Box::from_raw(array.private_data as *mut ArrayPrivate) };
+    for child in private.children.iter() {
+        let mut child = unsafe { Box::from_raw(*child) };
+        if let Some(release) = child.release {
+            unsafe { release(&mut *child) };
+        }
+    }
+    array.release = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn str_at(ptr: *const c_char) -> &'static str {
+        unsafe { CStr::from_ptr(ptr) }.to_str().unwrap()
+    }
+
+    fn schema_child(schema: &FFI_ArrowSchema, i: usize) -> &FFI_ArrowSchema {
+        assert!(i < schema.n_children as usize);
+        unsafe { &**schema.children.add(i) }
+    }
+
+    fn array_child(array: &FFI_ArrowArray, i: usize) -> &FFI_ArrowArray {
+        assert!(i < array.n_children as usize);
+        unsafe { &**array.children.add(i) }
+    }
+
+    fn values<T: Copy>(array: &FFI_ArrowArray) -> Vec<T> {
+        assert_eq!(array.n_buffers, 2);
+        let buffers = unsafe { std::slice::from_raw_parts(array.buffers, 2) };
+        assert!(buffers[0].is_null(), "columns have no validity bitmap");
+        unsafe { std::slice::from_raw_parts(buffers[1] as *const T, array.length as usize) }
+            .to_vec()
+    }
+
+    fn release(mut schema: FFI_ArrowSchema, mut array: FFI_ArrowArray) {
+        unsafe {
+            schema.release.unwrap()(&mut schema);
+            array.release.unwrap()(&mut array);
+        }
+        assert!(schema.release.is_none() && array.release.is_none());
+    }
+
+    fn batch() -> ArrowRecordBatch {
+        ArrowRecordBatch::new(
+            2,
+            vec![
+                ("id".into(), ArrowColumn::from_vec(vec![7u64, 8], 1)),
+                (
+                    "pos".into(),
+                    ArrowColumn::from_vec(vec![1f32, 2., 3., 4., 5., 6.], 3),
+                ),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn primitive_column() {
+        let column = ArrowColumn::from_vec(vec![1.5f64, 2.5, 3.5], 1);
+        let (schema, array) = (column.export_schema("mass"), column.export_array());
+
+        assert_eq!(str_at(schema.format), "g");
+        assert_eq!(str_at(schema.name), "mass");
+        assert_eq!(schema.n_children, 0);
+        assert_eq!((array.length, array.null_count, array.offset), (3, 0, 0));
+        assert_eq!(array.n_children, 0);
+        assert_eq!(values::<f64>(&array), [1.5, 2.5, 3.5]);
+
+        assert_eq!(Arc::strong_count(&column.data), 2);
+        release(schema, array);
+        assert_eq!(Arc::strong_count(&column.data), 1);
+    }
+
+    #[test]
+    fn fixed_size_list_column() {
+        let column = ArrowColumn::from_vec(vec![1u32, 2, 3, 4, 5, 6], 3);
+        let (schema, array) = (column.export_schema("ids"), column.export_array());
+
+        assert_eq!(str_at(schema.format), "+w:3");
+        assert_eq!(str_at(schema.name), "ids");
+        let item = schema_child(&schema, 0);
+        assert_eq!(str_at(item.format), "I");
+        assert_eq!(str_at(item.name), "item");
+
+        // the list only has a validity buffer, the values are in its child
+        assert_eq!((array.length, array.n_buffers), (2, 1));
+        assert!(unsafe { *array.buffers }.is_null());
+        let items = array_child(&array, 0);
+        assert_eq!(items.length, 6);
+        assert_eq!(values::<u32>(items), [1, 2, 3, 4, 5, 6]);
+
+        release(schema, array);
+        assert_eq!(Arc::strong_count(&column.data), 1);
+    }
+
+    #[test]
+    fn record_batch() {
+        let batch = batch();
+        let (schema, array) = (batch.export_schema(), batch.export_array());
+
+        assert_eq!(str_at(schema.format), "+s");
+        assert_eq!(schema.n_children, 2);
+        assert_eq!(str_at(schema_child(&schema, 0).format), "L");
+        assert_eq!(str_at(schema_child(&schema, 1).format), "+w:3");
+        assert_eq!((array.length, array.n_buffers, array.n_children), (2, 1, 2));
+        assert_eq!(values::<u64>(array_child(&array, 0)), [7, 8]);
+        let pos = array_child(array_child(&array, 1), 0);
+        assert_eq!(values::<f32>(pos), [1., 2., 3., 4., 5., 6.]);
+
+        release(schema, array);
+        for (_, column) in &batch.columns {
+            assert_eq!(Arc::strong_count(&column.data), 1);
+        }
+    }
+
+    #[test]
+    fn release_after_children_are_moved() {
+        let batch = batch();
+        let (schema, array) = (batch.export_schema(), batch.export_array());
+
+        // consumers may move a child out and mark it released, the parent then only frees the moved struct
+        let (moved_schema, moved_array) = unsafe {
+            let schema_child = *schema.children.add(1);
+            let array_child = *array.children.add(1);
+            let moved = (std::ptr::read(schema_child), std::ptr::read(array_child));
+            (*schema_child).release = None;
+            (*array_child).release = None;
+            moved
+        };
+        release(schema, array);
+        assert_eq!(Arc::strong_count(&batch.columns[0].1.data), 1);
+        assert_eq!(Arc::strong_count(&batch.columns[1].1.data), 2);
+
+        assert_eq!(values::<f32>(array_child(&moved_array, 0)).len(), 6);
+        release(moved_schema, moved_array);
+        assert_eq!(Arc::strong_count(&batch.columns[1].1.data), 1);
+    }
+
+    #[test]
+    fn mismatched_rows() {
+        let columns = vec![("id".into(), ArrowColumn::from_vec(vec![1u8, 2, 3], 1))];
+        assert!(ArrowRecordBatch::new(2, columns).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "needs python")]
+    fn capsules() {
+        Python::initialize();
+        Python::attach(|py| {
+            let batch = Bound::new(py, batch()).unwrap();
+            let data = |i: usize| batch.borrow().columns[i].1.data.clone();
+            let (id, pos) = (data(0), data(1));
+
+            // unconsumed capsules release the structs when dropped
+            let capsules = batch.call_method0("__arrow_c_array__").unwrap();
+            let schema = batch.call_method0("__arrow_c_schema__").unwrap();
+            assert_eq!(Arc::strong_count(&pos), 3);
+            drop((capsules, schema));
+            assert_eq!(Arc::strong_count(&pos), 2);
+
+            // consumers move the array out, the capsule then leaves it alone
+            let (_, array) = batch
+                .call_method0("__arrow_c_array__")
+                .unwrap()
+                .extract::<(Bound<'_, PyCapsule>, Bound<'_, PyCapsule>)>()
+                .unwrap();
+            let ptr = array.pointer_checked(Some(c"arrow_array")).unwrap();
+            let mut moved = unsafe {
+                let ptr = ptr.as_ptr() as *mut FFI_ArrowArray;
+                let moved = std::ptr::read(ptr);
+                (*ptr).release = None;
+                moved
+            };
+            drop(array);
+            assert_eq!(Arc::strong_count(&id), 3);
+            unsafe { moved.release.unwrap()(&mut moved) };
+            assert_eq!(Arc::strong_count(&id), 2);
+        });
+    }
+}
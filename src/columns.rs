@@ -0,0 +1,290 @@
+use crate::{
+    world_ref::UnsafeWorldRef, ArrowColumn, ArrowRecordBatch, AsBufferSlice, BufferElement,
+    BufferView,
+};
+use bevy::{ecs::component::Mutable, prelude::*};
+use pyo3::{
+    buffer::PyBuffer,
+    exceptions::{PyKeyError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+
+/// Struct fields that fill a fixed width row of a column, ex: f32 is 1 wide and Vec3 is 3 wide
+pub trait ColumnField: Sized {
+    type Element: BufferElement + pyo3::buffer::Element;
+    const WIDTH: usize;
+
+    fn write_row(&self, row: &mut [Self::Element]);
+    /// The field holding a row, `row` is always WIDTH long
+    fn from_row(row: &[Self::Element]) -> Self;
+}
+
+macro_rules! impl_column_field_scalar {
+    ($($ty:ty),* $(,)?) => {
+        $(impl ColumnField for $ty {
+            type Element = $ty;
+            const WIDTH: usize = 1;
+
+            fn write_row(&self, row: &mut [$ty]) {
+                row[0] = *self;
+            }
+            fn from_row(row: &[$ty]) -> Self {
+                row[0]
+            }
+        })*
+    };
+}
+
+impl_column_field_scalar!(i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+
+macro_rules! impl_column_field_slice {
+    ($($ty:ty => [$elem:ty; $n:literal]),* $(,)?) => {
+        $(impl ColumnField for $ty {
+            type Element = $elem;
+            const WIDTH: usize = $n;
+
+            fn write_row(&self, row: &mut [$elem]) {
+                row.copy_from_slice(self.as_buffer_slice());
+            }
+            fn from_row(row: &[$elem]) -> Self {
+                let row: [$elem; $n] = row.try_into().unwrap();
+                Self::from(row)
+            }
+        })*
+    };
+}
+
+impl<T: BufferElement + pyo3::buffer::Element, const N: usize> ColumnField for [T; N] {
+    type Element = T;
+    const WIDTH: usize = N;
+
+    fn write_row(&self, row: &mut [T]) {
+        row.copy_from_slice(self);
+    }
+    fn from_row(row: &[T]) -> Self {
+        row.try_into().unwrap()
+    }
+}
+
+impl_column_field_slice!(
+    bevy::math::Vec2 => [f32; 2],
+    bevy::math::Vec3 => [f32; 3],
+    bevy::math::Vec3A => [f32; 3],
+    bevy::math::Vec4 => [f32; 4],
+    bevy::math::DVec2 => [f64; 2],
+    bevy::math::DVec3 => [f64; 3],
+    bevy::math::DVec4 => [f64; 4],
+    bevy::math::IVec2 => [i32; 2],
+    bevy::math::IVec3 => [i32; 3],
+    bevy::math::IVec4 => [i32; 4],
+    bevy::math::UVec2 => [u32; 2],
+    bevy::math::UVec3 => [u32; 3],
+    bevy::math::UVec4 => [u32; 4],
+);
+
+/// Writes one row of a parsed column into a component
+pub type ColumnWriter<C> = Box<dyn Fn(&mut C, usize)>;
+
+pub(crate) type ReadColumnsFn =
+    fn(Python<'_>, UnsafeWorldRef, &[String]) -> PyResult<(Py<PyAny>, Py<PyDict>)>;
+pub(crate) type WriteColumnsFn =
+    fn(UnsafeWorldRef, &Bound<'_, PyAny>, &Bound<'_, PyDict>) -> PyResult<()>;
+pub(crate) type ReadArrowFn = fn(Python<'_>, UnsafeWorldRef) -> PyResult<Py<PyAny>>;
+
+/// Pack a field of every component into a numpy array, shaped (rows,) for 1 wide fields and (rows, width) otherwise
+pub fn column_to_numpy<'py, C, F: ColumnField>(
+    py: Python<'py>,
+    comps: &[&C],
+    get: fn(&C) -> &F,
+) -> PyResult<Bound<'py, PyAny>> {
+    let mut data = vec![F::Element::default(); comps.len() * F::WIDTH];
+    for (comp, row) in comps.iter().zip(data.chunks_exact_mut(F::WIDTH)) {
+        get(comp).write_row(row);
+    }
+
+    let view = match F::WIDTH {
+        1 => BufferView::from_vec(data, &[comps.len()])?,
+        width => BufferView::from_vec(data, &[comps.len(), width])?,
+    };
+    view.into_numpy(py)
+}
+
+/// Parse a contiguous buffer (ex: a numpy array of the field's dtype) with one row per component
+///
+/// Every row is checked with the field's `validate` here, so nothing is written if any row fails
+pub fn column_writer<C: 'static, F: ColumnField + 'static>(
+    column: &Bound<'_, PyAny>,
+    rows: usize,
+    get: fn(&mut C) -> &mut F,
+    validate: Option<fn(&F) -> PyResult<()>>,
+) -> PyResult<ColumnWriter<C>> {
+    let data = PyBuffer::<F::Element>::get(column)?.to_vec(column.py())?;
+    if data.len() != rows * F::WIDTH {
+        return Err(PyValueError::new_err(format!(
+            "Expected {} rows of {} elements, got {} elements",
+            rows,
+            F::WIDTH,
+            data.len()
+        )));
+    }
+
+    if let Some(validate) = validate {
+        for row in data.chunks_exact(F::WIDTH) {
+            validate(&F::from_row(row))?;
+        }
+    }
+
+    Ok(Box::new(move |comp, row| {
+        *get(comp) = F::from_row(&data[row * F::WIDTH..(row + 1) * F::WIDTH]);
+    }))
+}
+
+/// Batch access to a component's `#[py_bevy(column)]` fields across every entity
+pub trait BevyPyColumns: Component<Mutability = Mutable> + Sized {
+    /// Python names of the column fields
+    const COLUMNS: &'static [&'static str];
+
+    /// None if `field` isn't a column
+    fn column_to_numpy<'py>(
+        py: Python<'py>,
+        field: &str,
+        comps: &[&Self],
+    ) -> Option<PyResult<Bound<'py, PyAny>>>;
+    /// None if `field` isn't a column
+    fn column_writer(
+        field: &str,
+        column: &Bound<'_, PyAny>,
+        rows: usize,
+    ) -> Option<PyResult<ColumnWriter<Self>>>;
+
+    /// None if `field` isn't a column
+    fn column_to_arrow(field: &str, comps: &[&Self]) -> Option<ArrowColumn>;
+
+    /// Entity ids (Entity::to_bits) of every entity with the component, and a dict of a numpy array per field
+    fn read_columns(
+        py: Python<'_>,
+        world_ref: UnsafeWorldRef,
+        fields: &[String],
+    ) -> PyResult<(Py<PyAny>, Py<PyDict>)> {
+        world_ref.map_to_world(|world| {
+            let mut query = world.query::<(Entity, &Self)>();
+            let (entities, comps): (Vec<Entity>, Vec<&Self>) = query.iter(world).unzip();
+
+            let columns = PyDict::new(py);
+            for field in fields {
+                let column = Self::column_to_numpy(py, field, &comps).unwrap_or_else(|| {
+                    Err(PyKeyError::new_err(format!("{} is not a column", field)))
+                })?;
+                columns.set_item(field, column)?;
+            }
+
+            let ids: Vec<u64> = entities.iter().map(|e| e.to_bits()).collect();
+            let ids = BufferView::from_vec(ids, &[entities.len()])?.into_numpy(py)?;
+            Ok((ids.unbind(), columns.unbind()))
+        })
+    }
+
+    /// Arrow record batch with an `entity` column (Entity::to_bits) and every column field, one row per entity
+    fn read_arrow(py: Python<'_>, world_ref: UnsafeWorldRef) -> PyResult<Py<PyAny>> {
+        world_ref.map_to_world(|world| {
+            let mut query = world.query::<(Entity, &Self)>();
+            let (entities, comps): (Vec<Entity>, Vec<&Self>) = query.iter(world).unzip();
+
+            let ids: Vec<u64> = entities.iter().map(|e| e.to_bits()).collect();
+            let mut columns = vec![("entity".to_string(), ArrowColumn::from_vec(ids, 1))];
+            for field in Self::COLUMNS {
+                if let Some(column) = Self::column_to_arrow(field, &comps) {
+                    columns.push((field.to_string(), column));
+                }
+            }
+
+            let batch = ArrowRecordBatch::new(entities.len(), columns)?;
+            Ok(Py::new(py, batch)?.into_any())
+        })
+    }
+
+    /// Write each column back into the entity in the same row, nothing is written if any entity or column is invalid
+    fn write_columns(
+        world_ref: UnsafeWorldRef,
+        entities: &Bound<'_, PyAny>,
+        columns: &Bound<'_, PyDict>,
+    ) -> PyResult<()> {
+        let entities = entities
+            .extract::<Vec<u64>>()?
+            .into_iter()
+            .map(|id| {
+                Entity::try_from_bits(id)
+                    .ok_or_else(|| PyValueError::new_err(format!("{} is not an entity id", id)))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let writers = columns
+            .iter()
+            .map(|(field, column)| {
+                let field: String = field.extract()?;
+                Self::column_writer(&field, &column, entities.len()).unwrap_or_else(|| {
+                    Err(PyKeyError::new_err(format!("{} is not a column", field)))
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        world_ref.map_to_world(|world| {
+            if let Some(entity) = entities.iter().find(|e| world.get::<Self>(**e).is_none()) {
+                return Err(PyValueError::new_err(format!(
+                    "Entity {entity} doesn't have the component"
+                )));
+            }
+            for (row, entity) in entities.iter().enumerate() {
+                let mut comp = world.get_mut::<Self>(*entity).unwrap();
+                for writer in &writers {
+                    writer(&mut *comp, row);
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// The column fields of every entity with a component, handed to python by PyObjectRegistry::comp_columns
+#[pyclass(unsendable)]
+pub struct ComponentColumns {
+    world: UnsafeWorldRef,
+    read_columns: ReadColumnsFn,
+    write_columns: WriteColumnsFn,
+    read_arrow: ReadArrowFn,
+}
+
+impl ComponentColumns {
+    pub(crate) fn new(
+        world: UnsafeWorldRef,
+        read_columns: ReadColumnsFn,
+        write_columns: WriteColumnsFn,
+        read_arrow: ReadArrowFn,
+    ) -> Self {
+        Self {
+            world,
+            read_columns,
+            write_columns,
+            read_arrow,
+        }
+    }
+}
+
+#[pymethods]
+impl ComponentColumns {
+    /// Entity ids and a dict of a numpy array per field
+    fn read(&self, py: Python<'_>, fields: Vec<String>) -> PyResult<(Py<PyAny>, Py<PyDict>)> {
+        (self.read_columns)(py, self.world.clone(), &fields)
+    }
+
+    /// Write a dict of field name to array back into `entities`, row by row
+    fn write(&self, entities: &Bound<'_, PyAny>, columns: &Bound<'_, PyDict>) -> PyResult<()> {
+        (self.write_columns)(self.world.clone(), entities, columns)
+    }
+
+    /// Arrow record batch of the entity ids and every column field
+    fn read_arrow(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        (self.read_arrow)(py, self.world.clone())
+    }
+}
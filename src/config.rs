@@ -0,0 +1,442 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Why a config couldn't be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The env var pointing to the config isn't set or isn't unicode
+    Env {
+        var: String,
+        source: std::env::VarError,
+    },
+    /// The config file couldn't be read
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The config file isn't valid for the config struct, line and column are 1 based
+    Parse {
+        path: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// The file's format isn't one the config accepts, or its cargo feature is off
+    UnsupportedFormat { path: PathBuf, format: String },
+    /// The merged config layers don't fit the config struct
+    Merge { message: String },
+    /// A file's `extends` key isn't a path or list of paths
+    Extends { path: PathBuf, message: String },
+    /// Files extend each other in a loop, the first file is repeated at the end
+    ExtendsCycle { chain: Vec<PathBuf> },
+    /// The config couldn't be serialized in the file's format
+    Serialize { path: PathBuf, message: String },
+    /// The config file doesn't match the config's schema, every mismatch is listed
+    Invalid { path: PathBuf, errors: Vec<String> },
+    /// The saved config couldn't be written
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Env { var, source } => {
+                write!(f, "Failed to read env var {}: {}", var, source)
+            }
+            ConfigError::Io { path, source } => {
+                write!(f, "Failed to read file {:?}: {}", path, source)
+            }
+            ConfigError::Parse {
+                path,
+                line: Some(line),
+                column: Some(column),
+                message,
+            } => write!(
+                f,
+                "Failed to parse {:?} at {}:{}: {}",
+                path, line, column, message
+            ),
+            ConfigError::Parse { path, message, .. } => {
+                write!(f, "Failed to parse {:?}: {}", path, message)
+            }
+            ConfigError::UnsupportedFormat { path, format } => {
+                write!(
+                    f,
+                    "Can't load {:?}, {} configs aren't supported",
+                    path, format
+                )
+            }
+            ConfigError::Merge { message } => {
+                write!(f, "Failed to merge config layers: {}", message)
+            }
+            ConfigError::Extends { path, message } => {
+                write!(f, "Invalid extends in {:?}: {}", path, message)
+            }
+            ConfigError::ExtendsCycle { chain } => {
+                let chain: Vec<_> = chain.iter().map(|path| format!("{:?}", path)).collect();
+                write!(f, "Config files extend each other: {}", chain.join(" -> "))
+            }
+            ConfigError::Serialize { path, message } => {
+                write!(f, "Failed to serialize config for {:?}: {}", path, message)
+            }
+            ConfigError::Invalid { path, errors } => {
+                write!(f, "{:?} doesn't match the config schema:", path)?;
+                errors
+                    .iter()
+                    .try_for_each(|error| write!(f, "\n    {}", error))
+            }
+            ConfigError::Write { path, source } => {
+                write!(f, "Failed to write file {:?}: {}", path, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Env { source, .. } => Some(source),
+            ConfigError::Io { source, .. } | ConfigError::Write { source, .. } => Some(source),
+            ConfigError::Parse { .. }
+            | ConfigError::UnsupportedFormat { .. }
+            | ConfigError::Merge { .. }
+            | ConfigError::Extends { .. }
+            | ConfigError::ExtendsCycle { .. }
+            | ConfigError::Serialize { .. }
+            | ConfigError::Invalid { .. } => None,
+        }
+    }
+}
+
+// io errors keep their python class (ex: FileNotFoundError), parse errors are ValueErrors
+#[cfg(feature = "minimal-pyo3")]
+impl From<ConfigError> for pyo3::PyErr {
+    fn from(err: ConfigError) -> Self {
+        let message = err.to_string();
+        match err {
+            ConfigError::Env { .. } => pyo3::exceptions::PyKeyError::new_err(message),
+            ConfigError::Io { source, .. } | ConfigError::Write { source, .. } => {
+                std::io::Error::new(source.kind(), message).into()
+            }
+            ConfigError::Parse { .. }
+            | ConfigError::UnsupportedFormat { .. }
+            | ConfigError::Merge { .. }
+            | ConfigError::Extends { .. }
+            | ConfigError::ExtendsCycle { .. }
+            | ConfigError::Serialize { .. }
+            | ConfigError::Invalid { .. } => pyo3::exceptions::PyValueError::new_err(message),
+        }
+    }
+}
+
+/// A file read while loading a config and its modified time from just before it was read
+pub type ConfigFile = (PathBuf, Option<std::time::SystemTime>);
+
+thread_local! {
+    // one list per track_config_files call in progress
+    static TRACKED_FILES: std::cell::RefCell<Vec<Vec<ConfigFile>>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Run `load`, returning every config file it read, ex: the files a config `extends`
+pub fn track_config_files<R>(load: impl FnOnce() -> R) -> (R, Vec<ConfigFile>) {
+    TRACKED_FILES.with(|tracked| tracked.borrow_mut().push(Vec::new()));
+    let result = load();
+    let files = TRACKED_FILES.with(|tracked| tracked.borrow_mut().pop().unwrap_or_default());
+    (result, files)
+}
+
+pub(crate) fn read_config(path: &Path) -> Result<String, ConfigError> {
+    let modified = std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok();
+    TRACKED_FILES.with(|tracked| {
+        for files in tracked.borrow_mut().iter_mut() {
+            files.push((path.to_path_buf(), modified));
+        }
+    });
+    std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Read and deserialize a config file, merging the files it `extends` underneath it (needs the yaml feature)
+pub fn load_config<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    format: ConfigFormat,
+) -> Result<T, ConfigError> {
+    let contents = read_config(path)?;
+
+    #[cfg(feature = "yaml")]
+    {
+        let (value, extended) = crate::config_layers::parse_config_value(path, format, &contents)?;
+        serde_yaml::from_value(value).or_else(|what| match extended {
+            // line numbers are lost once files are merged
+            true => Err(ConfigError::Parse {
+                path: path.to_path_buf(),
+                line: None,
+                column: None,
+                message: what.to_string(),
+            }),
+            // the value has no line numbers, parsing the file straight into T finds where it failed
+            false => format.parse(path, &contents),
+        })
+    }
+    #[cfg(not(feature = "yaml"))]
+    format.parse(path, &contents)
+}
+
+/// Serialize a config into `path`, paths under the file's directory are written relative to it with `relative_paths`
+pub fn save_config<T: serde::Serialize + Clone + crate::MakePathsAbsolute>(
+    config: &T,
+    path: &Path,
+    format: ConfigFormat,
+    relative_paths: bool,
+) -> Result<(), ConfigError> {
+    let contents = match relative_paths {
+        true => {
+            let mut config = config.clone();
+            let config_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            config.make_paths_relative(&config_dir);
+            format.serialize(path, &config)?
+        }
+        false => format.serialize(path, config)?,
+    };
+    std::fs::write(path, contents).map_err(|source| ConfigError::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Config file formats, each is parsed only when its cargo feature is on (yaml is on by default)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+    Ron,
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+            ConfigFormat::Ron => "ron",
+        })
+    }
+}
+
+impl ConfigFormat {
+    pub const ALL: &'static [ConfigFormat] = &[
+        ConfigFormat::Yaml,
+        ConfigFormat::Toml,
+        ConfigFormat::Json,
+        ConfigFormat::Ron,
+    ];
+
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ConfigFormat::Yaml => &["yaml", "yml"],
+            ConfigFormat::Toml => &["toml"],
+            ConfigFormat::Json => &["json"],
+            ConfigFormat::Ron => &["ron"],
+        }
+    }
+
+    /// Format matching the path's extension, None for unknown extensions
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.extensions().contains(&ext.as_str()))
+    }
+
+    /// Pick one of `accepted` by the path's extension, unknown extensions fall back to the first accepted format
+    pub fn detect(path: &Path, accepted: &[ConfigFormat]) -> Result<Self, ConfigError> {
+        match Self::from_path(path) {
+            Some(format) if accepted.contains(&format) => Ok(format),
+            Some(format) => Err(ConfigError::UnsupportedFormat {
+                path: path.to_path_buf(),
+                format: format.to_string(),
+            }),
+            None => accepted
+                .first()
+                .copied()
+                .ok_or_else(|| ConfigError::UnsupportedFormat {
+                    path: path.to_path_buf(),
+                    format: "any".to_string(),
+                }),
+        }
+    }
+
+    /// Deserialize `contents` read from `path`, errors carry the line and column when the parser reports one
+    #[allow(unused_variables)]
+    pub fn parse<T: serde::de::DeserializeOwned>(
+        self,
+        path: &Path,
+        contents: &str,
+    ) -> Result<T, ConfigError> {
+        let parse_err =
+            |line: Option<usize>, column: Option<usize>, message: String| ConfigError::Parse {
+                path: path.to_path_buf(),
+                line,
+                column,
+                message,
+            };
+
+        match self {
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => serde_yaml::from_str(contents).map_err(|what| {
+                let location = what.location();
+                parse_err(
+                    location.as_ref().map(|loc| loc.line()),
+                    location.as_ref().map(|loc| loc.column()),
+                    what.to_string(),
+                )
+            }),
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => toml::from_str(contents).map_err(|what| {
+                let (line, column) = match what.span() {
+                    Some(span) => line_column(contents, span.start),
+                    None => (None, None),
+                };
+                parse_err(line, column, what.message().to_string())
+            }),
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => serde_json::from_str(contents).map_err(|what| {
+                // serde_json reports line 0 for errors that aren't tied to the input
+                let line = (what.line() > 0).then_some(what.line());
+                parse_err(line, line.map(|_| what.column()), what.to_string())
+            }),
+            #[cfg(feature = "ron")]
+            ConfigFormat::Ron => ron::from_str(contents).map_err(|what| {
+                parse_err(
+                    Some(what.span.start.line),
+                    Some(what.span.start.col),
+                    what.code.to_string(),
+                )
+            }),
+            #[allow(unreachable_patterns)]
+            format => Err(format.disabled(path)),
+        }
+    }
+
+    /// Serialize `config` to be written to `path`
+    #[allow(unused_variables)]
+    pub fn serialize<T: serde::Serialize>(
+        self,
+        path: &Path,
+        config: &T,
+    ) -> Result<String, ConfigError> {
+        let serialize_err = |message: String| ConfigError::Serialize {
+            path: path.to_path_buf(),
+            message,
+        };
+
+        match self {
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => {
+                serde_yaml::to_string(config).map_err(|what| serialize_err(what.to_string()))
+            }
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => {
+                toml::to_string_pretty(config).map_err(|what| serialize_err(what.to_string()))
+            }
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(config).map_err(|what| serialize_err(what.to_string()))
+            }
+            #[cfg(feature = "ron")]
+            ConfigFormat::Ron => ron::ser::to_string_pretty(config, Default::default())
+                .map_err(|what| serialize_err(what.to_string())),
+            #[allow(unreachable_patterns)]
+            format => Err(format.disabled(path)),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn disabled(self, path: &Path) -> ConfigError {
+        ConfigError::UnsupportedFormat {
+            path: path.to_path_buf(),
+            format: format!("{} (enable the {} feature)", self, self),
+        }
+    }
+}
+
+// 1 based line and column of a byte offset
+#[cfg(feature = "toml")]
+fn line_column(contents: &str, offset: usize) -> (Option<usize>, Option<usize>) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (Some(line), Some(column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unsupported_format(result: Result<ConfigFormat, ConfigError>) -> String {
+        match result {
+            Err(ConfigError::UnsupportedFormat { format, .. }) => format,
+            other => panic!("expected UnsupportedFormat, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn detect_by_extension() {
+        let accepted = [ConfigFormat::Toml, ConfigFormat::Yaml];
+        let detect = |path: &str| ConfigFormat::detect(Path::new(path), &accepted);
+
+        assert_eq!(detect("cfg/app.yml").unwrap(), ConfigFormat::Yaml);
+        assert_eq!(detect("app.yaml").unwrap(), ConfigFormat::Yaml);
+        assert_eq!(detect("APP.TOML").unwrap(), ConfigFormat::Toml);
+        // unknown or missing extensions use the first accepted format
+        assert_eq!(detect("app.cfg").unwrap(), ConfigFormat::Toml);
+        assert_eq!(detect("app").unwrap(), ConfigFormat::Toml);
+        assert_eq!(unsupported_format(detect("app.json")), "json");
+    }
+
+    #[test]
+    fn detect_without_accepted_formats() {
+        let detect = |path: &str| ConfigFormat::detect(Path::new(path), &[]);
+
+        assert_eq!(unsupported_format(detect("app")), "any");
+        assert_eq!(unsupported_format(detect("app.yaml")), "yaml");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_line_column() {
+        let contents = "a = 1\nbc = [\n";
+        assert_eq!(line_column(contents, 0), (Some(1), Some(1)));
+        assert_eq!(line_column(contents, 6), (Some(2), Some(1)));
+        assert_eq!(line_column(contents, 11), (Some(2), Some(6)));
+        // offsets past the end are clamped
+        assert_eq!(line_column(contents, 100), (Some(3), Some(1)));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_errors_have_line_numbers() {
+        let path = Path::new("app.toml");
+        let parsed = ConfigFormat::Toml
+            .parse::<std::collections::HashMap<String, f64>>(path, "a = 1.0\nb = \"x\"\n");
+        match parsed {
+            Err(ConfigError::Parse {
+                line: Some(2),
+                column: Some(5),
+                ..
+            }) => {}
+            other => panic!("expected a parse error at 2:5, got {:?}", other),
+        }
+    }
+}
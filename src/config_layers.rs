@@ -0,0 +1,515 @@
+use crate::{config::read_config, ConfigError, ConfigFormat, MakePathsAbsolute};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+/// Builds a config by merging layers, later layers override the fields they set
+///
+/// Start from `T::default()`, then merge files, env vars and explicit values in the order they're added.
+/// Mappings merge key by key, anything else (including lists) is replaced.
+/// Relative paths from a file are made absolute from that file's directory, other paths from the last file's
+pub struct ConfigLayers<T> {
+    value: Value,
+    // same shape as value, each leaf is the directory of the file that set it or null
+    dirs: Value,
+    config_dir: Option<PathBuf>,
+    _config: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default + MakePathsAbsolute> ConfigLayers<T> {
+    pub fn new() -> Result<Self, ConfigError> {
+        let value = serde_yaml::to_value(T::default()).map_err(|what| ConfigError::Merge {
+            message: format!("default: {}", what),
+        })?;
+        Ok(Self {
+            value,
+            dirs: Value::Null,
+            config_dir: None,
+            _config: PhantomData,
+        })
+    }
+
+    /// Merge a file in one of `formats`, picked by its extension
+    pub fn file(
+        mut self,
+        path: impl AsRef<Path>,
+        formats: &[ConfigFormat],
+    ) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let format = ConfigFormat::detect(path, formats)?;
+        let (layer, dirs) = load_extended(path, format, &mut Vec::new())?;
+
+        merge(&mut self.value, layer);
+        merge(&mut self.dirs, dirs);
+        self.config_dir = Some(path.parent().unwrap_or(Path::new("")).to_path_buf());
+        Ok(self)
+    }
+
+    /// Merge env vars named `{prefix}__{field}__{nested_field}`, ex: MYAPP__PHYSICS__GRAVITY=9.8
+    ///
+    /// Names match the serde field names ignoring case, `_` and `-`, so MYAPP__MAX_SPEED sets `maxSpeed`.
+    /// Values are parsed as yaml unless the field they replace is a string.
+    /// Vars with names or values that aren't unicode are skipped
+    pub fn env(self, prefix: &str) -> Self {
+        let prefix = format!("{}__", prefix);
+        let mut vars: Vec<_> = std::env::vars_os()
+            .filter_map(|(var, val)| {
+                let var = var.to_str()?.strip_prefix(&prefix)?.to_string();
+                Some((var, val.into_string().ok()?))
+            })
+            .collect();
+        // deterministic order when a var sets a parent of another
+        vars.sort();
+
+        vars.into_iter().fold(self, |layers, (var, val)| {
+            let keys = layers.env_keys(&var);
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let replaces_string = matches!(layers.get(&keys), Some(Value::String(_)));
+            let val = match replaces_string {
+                true => Value::String(val),
+                false => serde_yaml::from_str(&val).unwrap_or(Value::String(val)),
+            };
+            layers.set(&keys, val)
+        })
+    }
+
+    // the existing keys an env var's `__` separated names refer to, unknown names are lowercased
+    fn env_keys(&self, var: &str) -> Vec<String> {
+        let normalize = |key: &str| -> String {
+            key.chars()
+                .filter(|c| !matches!(c, '_' | '-'))
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+        let mut node = Some(&self.value);
+        var.split("__")
+            .map(|name| {
+                let key = node
+                    .and_then(Value::as_mapping)
+                    .and_then(|mapping| {
+                        mapping
+                            .keys()
+                            .filter_map(Value::as_str)
+                            .find(|key| normalize(key) == normalize(name))
+                    })
+                    .map(str::to_string)
+                    .unwrap_or_else(|| name.to_lowercase());
+                node = node.and_then(|node| node.get(&key));
+                key
+            })
+            .collect()
+    }
+
+    /// Override a (nested) field
+    pub fn set(mut self, keys: &[&str], val: Value) -> Self {
+        let layer = keys.iter().rev().fold(val, |val, key| {
+            let mut mapping = Mapping::new();
+            mapping.insert(Value::String(key.to_string()), val);
+            Value::Mapping(mapping)
+        });
+        merge(&mut self.dirs, with_leaves(&layer, &Value::Null));
+        merge(&mut self.value, layer);
+        self
+    }
+
+    /// The merged value of a (nested) field
+    pub fn get(&self, keys: &[&str]) -> Option<&Value> {
+        keys.iter()
+            .try_fold(&self.value, |value, key| value.get(*key))
+    }
+
+    pub fn build(self) -> Result<T, ConfigError> {
+        resolve_paths(self.value, &self.dirs, self.config_dir.as_deref()).map_err(|what| {
+            ConfigError::Merge {
+                message: what.to_string(),
+            }
+        })
+    }
+}
+
+/// Deserialize a merged value, with each relative path made absolute from the directory in `dirs` at the same key,
+/// or `fallback_dir` where no file set it
+fn resolve_paths<T: Serialize + DeserializeOwned + MakePathsAbsolute>(
+    value: Value,
+    dirs: &Value,
+    fallback_dir: Option<&Path>,
+) -> Result<T, serde_yaml::Error> {
+    let mut all_dirs = Vec::new();
+    collect_dirs(dirs, &mut all_dirs);
+    if let Some(fallback_dir) = fallback_dir {
+        if !all_dirs.iter().any(|dir| dir == fallback_dir) {
+            all_dirs.push(fallback_dir.to_path_buf());
+        }
+    }
+    if all_dirs.len() <= 1 {
+        let mut cfg: T = serde_yaml::from_value(value)?;
+        if let Some(dir) = all_dirs.first() {
+            cfg.make_paths_absolute(dir);
+        }
+        return Ok(cfg);
+    }
+
+    // the whole config resolved from each directory, each field then comes from its own directory's version
+    let resolved = all_dirs
+        .into_iter()
+        .map(|dir| {
+            let mut cfg: T = serde_yaml::from_value(value.clone())?;
+            cfg.make_paths_absolute(&dir);
+            Ok((dir, serde_yaml::to_value(cfg)?))
+        })
+        .collect::<Result<Vec<_>, serde_yaml::Error>>()?;
+    let value = pick_resolved(value, dirs, fallback_dir, &resolved, &mut Vec::new());
+    serde_yaml::from_value(value)
+}
+
+fn collect_dirs(dirs: &Value, all_dirs: &mut Vec<PathBuf>) {
+    match dirs {
+        Value::Mapping(mapping) => mapping
+            .values()
+            .for_each(|dirs| collect_dirs(dirs, all_dirs)),
+        Value::String(dir) if !all_dirs.iter().any(|d| d.as_os_str() == dir.as_str()) => {
+            all_dirs.push(PathBuf::from(dir))
+        }
+        _ => {}
+    }
+}
+
+// swap each leaf of value for the one in the version resolved from the directory that set it
+fn pick_resolved(
+    value: Value,
+    dirs: &Value,
+    fallback_dir: Option<&Path>,
+    resolved: &[(PathBuf, Value)],
+    keys: &mut Vec<Value>,
+) -> Value {
+    match (value, dirs) {
+        (Value::Mapping(mapping), Value::Mapping(dirs)) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, val)| {
+                    let val_dirs = dirs.get(&key).unwrap_or(&Value::Null);
+                    keys.push(key.clone());
+                    let val = pick_resolved(val, val_dirs, fallback_dir, resolved, keys);
+                    keys.pop();
+                    (key, val)
+                })
+                .collect(),
+        ),
+        (value, dirs) => {
+            let dir = dirs.as_str().map(Path::new).or(fallback_dir);
+            resolved
+                .iter()
+                .find(|(resolved_dir, _)| Some(resolved_dir.as_path()) == dir)
+                .and_then(|(_, version)| keys.iter().try_fold(version, |node, key| node.get(key)))
+                .cloned()
+                .unwrap_or(value)
+        }
+    }
+}
+
+const EXTENDS_KEY: &str = "extends";
+
+/// Parse a config file, with the files named by its `extends` key merged underneath it
+///
+/// `extends` is a path or list of paths relative to the file, later bases override earlier ones.
+/// Bases use their own extension's format, falling back to `format`
+pub fn load_config_value(path: &Path, format: ConfigFormat) -> Result<Value, ConfigError> {
+    load_extended(path, format, &mut Vec::new()).map(|(value, _)| value)
+}
+
+/// Parse a file's contents with its extended files merged underneath, and whether it extends any
+pub(crate) fn parse_config_value(
+    path: &Path,
+    format: ConfigFormat,
+    contents: &str,
+) -> Result<(Value, bool), ConfigError> {
+    let value: Value = format.parse(path, contents)?;
+    let extended = value.get(EXTENDS_KEY).is_some();
+    let id = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let (value, _) = extend(path, format, value, &mut vec![id])?;
+    Ok((value, extended))
+}
+
+// chain holds the files currently being extended, to catch cycles
+fn load_extended(
+    path: &Path,
+    format: ConfigFormat,
+    chain: &mut Vec<PathBuf>,
+) -> Result<(Value, Value), ConfigError> {
+    let id = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if let Some(start) = chain.iter().position(|file| *file == id) {
+        let mut cycle = chain[start..].to_vec();
+        cycle.push(id);
+        return Err(ConfigError::ExtendsCycle { chain: cycle });
+    }
+
+    let value: Value = format.parse(path, &read_config(path)?)?;
+    chain.push(id);
+    let merged = extend(path, format, value, chain);
+    chain.pop();
+    merged
+}
+
+// merge the bases named by value's extends key underneath it, path is the last file in chain.
+// Also returns the directory of the file that set each value, in the same shape as the value
+fn extend(
+    path: &Path,
+    format: ConfigFormat,
+    mut value: Value,
+    chain: &mut Vec<PathBuf>,
+) -> Result<(Value, Value), ConfigError> {
+    let config_dir = path.parent().unwrap_or(Path::new(""));
+    let bases = value
+        .as_mapping_mut()
+        .and_then(|mapping| mapping.remove(EXTENDS_KEY));
+    let dirs = with_leaves(&value, &Value::String(config_dir.to_string_lossy().into()));
+    let Some(bases) = bases else {
+        return Ok((value, dirs));
+    };
+
+    let extends_err = |message: &str| ConfigError::Extends {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    };
+    let bases = match bases {
+        Value::String(base) => vec![base],
+        Value::Sequence(bases) => bases
+            .into_iter()
+            .map(|base| match base {
+                Value::String(base) => Ok(base),
+                _ => Err(extends_err("expected a list of paths")),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => return Err(extends_err("expected a path or a list of paths")),
+    };
+
+    let mut merged = Value::Null;
+    let mut merged_dirs = Value::Null;
+    for base in bases {
+        let base_path = config_dir.join(base);
+        let base_format = ConfigFormat::from_path(&base_path).unwrap_or(format);
+        let (base_value, base_dirs) = load_extended(&base_path, base_format, chain)?;
+        merge(&mut merged, base_value);
+        merge(&mut merged_dirs, base_dirs);
+    }
+
+    merge(&mut merged, value);
+    merge(&mut merged_dirs, dirs);
+    Ok((merged, merged_dirs))
+}
+
+// the shape of value with every leaf replaced by leaf
+fn with_leaves(value: &Value, leaf: &Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .iter()
+                .map(|(key, val)| (key.clone(), with_leaves(val, leaf)))
+                .collect(),
+        ),
+        _ => leaf.clone(),
+    }
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Mapping(base), Value::Mapping(layer)) => {
+            for (key, val) in layer {
+                match base.get_mut(&key) {
+                    Some(base_val) => merge(base_val, val),
+                    None => {
+                        base.insert(key, val);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Convert python kwargs into a config layer value, ex: dicts, lists, numbers, strings and paths
+#[cfg(feature = "minimal-pyo3")]
+pub fn py_to_config_value(obj: &pyo3::Bound<'_, pyo3::PyAny>) -> pyo3::PyResult<Value> {
+    use pyo3::{
+        prelude::*,
+        types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+    };
+
+    if obj.is_none() {
+        Ok(Value::Null)
+    } else if obj.is_instance_of::<PyBool>() {
+        Ok(Value::Bool(obj.extract()?))
+    } else if obj.is_instance_of::<PyInt>() {
+        Ok(Value::Number(obj.extract::<i64>()?.into()))
+    } else if obj.is_instance_of::<PyFloat>() {
+        Ok(Value::Number(obj.extract::<f64>()?.into()))
+    } else if obj.is_instance_of::<PyString>() {
+        Ok(Value::String(obj.extract()?))
+    } else if let Ok(dict) = obj.cast::<PyDict>() {
+        dict.iter()
+            .map(|(key, val)| Ok((Value::String(key.extract()?), py_to_config_value(&val)?)))
+            .collect::<PyResult<Mapping>>()
+            .map(Value::Mapping)
+    } else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+        obj.try_iter()?
+            .map(|item| py_to_config_value(&item?))
+            .collect::<PyResult<Vec<_>>>()
+            .map(Value::Sequence)
+    } else if let Ok(path) = obj.extract::<PathBuf>() {
+        Ok(Value::String(path.to_string_lossy().into_owned()))
+    } else {
+        Err(pyo3::exceptions::PyTypeError::new_err(format!(
+            "Can't use {} as a config value",
+            obj.get_type().name()?
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Physics {
+        gravity: f64,
+        max_speed: f64,
+        mesh: PathBuf,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct TestConfig {
+        rate: f64,
+        #[serde(rename = "name")]
+        label: String,
+        steps: Vec<u32>,
+        enabled: bool,
+        data: PathBuf,
+        physics: Physics,
+    }
+
+    impl MakePathsAbsolute for TestConfig {
+        fn make_paths_absolute(&mut self, parent_path: &PathBuf) {
+            self.data.make_paths_absolute(parent_path);
+            self.physics.mesh.make_paths_absolute(parent_path);
+        }
+    }
+
+    fn yaml(text: &str) -> Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    // an empty directory per test, removed first in case an earlier run failed
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("simple_py_bevy_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn merge_maps_deeply_and_replace_sequences() {
+        let mut base = yaml("a: {b: 1, c: [1, 2]}\nd: x\n");
+        merge(&mut base, yaml("a: {c: [3], e: {f: 2}}\nd: {g: 1}\n"));
+
+        assert_eq!(base, yaml("a: {b: 1, c: [3], e: {f: 2}}\nd: {g: 1}\n"));
+    }
+
+    #[test]
+    fn env_values_take_the_field_type() {
+        let vars = [
+            ("RATE", "2.5"),
+            ("NAME", "123"),
+            ("STEPS", "[1, 2]"),
+            ("ENABLED", "true"),
+            ("PHYSICS__MAX_SPEED", "3"),
+        ];
+        for (var, val) in vars {
+            std::env::set_var(format!("LAYERS_ENV_TEST__{}", var), val);
+        }
+
+        let cfg: TestConfig = ConfigLayers::new()
+            .unwrap()
+            .env("LAYERS_ENV_TEST")
+            .build()
+            .unwrap();
+        assert_eq!(cfg.rate, 2.5);
+        // a number replacing a string field stays a string
+        assert_eq!(cfg.label, "123");
+        assert_eq!(cfg.steps, vec![1, 2]);
+        assert!(cfg.enabled);
+        // names are matched to the serde field names, ex: rename_all = "camelCase"
+        assert_eq!(cfg.physics.max_speed, 3.0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn env_skips_vars_that_arent_unicode() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = std::ffi::OsStr::from_bytes(b"\xff");
+        std::env::set_var("LAYERS_ENV_UNICODE_TEST__RATE", "2.5");
+        std::env::set_var("LAYERS_ENV_UNICODE_TEST__NAME", invalid);
+        std::env::set_var(
+            std::ffi::OsStr::from_bytes(b"LAYERS_ENV_UNICODE_TEST__\xff"),
+            "1",
+        );
+
+        let cfg: TestConfig = ConfigLayers::new()
+            .unwrap()
+            .env("LAYERS_ENV_UNICODE_TEST")
+            .build()
+            .unwrap();
+        assert_eq!(cfg.rate, 2.5);
+        assert_eq!(cfg.label, "");
+    }
+
+    #[test]
+    fn extends_cycle_is_an_error() {
+        let dir = test_dir("extends_cycle");
+        std::fs::write(dir.join("a.yaml"), "extends: b.yaml\nrate: 1\n").unwrap();
+        std::fs::write(dir.join("b.yaml"), "extends: [c.yaml]\n").unwrap();
+        std::fs::write(dir.join("c.yaml"), "extends: a.yaml\n").unwrap();
+
+        match load_config_value(&dir.join("a.yaml"), ConfigFormat::Yaml) {
+            Err(ConfigError::ExtendsCycle { chain }) => {
+                let names: Vec<_> = chain.iter().map(|path| path.file_name().unwrap()).collect();
+                assert_eq!(names, ["a.yaml", "b.yaml", "c.yaml", "a.yaml"]);
+            }
+            other => panic!("expected an extends cycle, got {:?}", other),
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn paths_are_absolute_from_the_file_that_set_them() {
+        let dir = test_dir("layer_paths");
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("app")).unwrap();
+        std::fs::write(dir.join("base/base.yaml"), "physics: {mesh: mesh.obj}\n").unwrap();
+        std::fs::write(
+            dir.join("app/app.yaml"),
+            "extends: ../base/base.yaml\ndata: data.bin\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("local.yaml"), "rate: 2\n").unwrap();
+
+        let cfg: TestConfig = ConfigLayers::new()
+            .unwrap()
+            .file(dir.join("app/app.yaml"), &[ConfigFormat::Yaml])
+            .unwrap()
+            .file(dir.join("local.yaml"), &[ConfigFormat::Yaml])
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(cfg.data, dir.join("app/data.bin"));
+        assert_eq!(cfg.physics.mesh, dir.join("app/../base/mesh.obj"));
+        assert_eq!(cfg.rate, 2.0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{track_config_files, ConfigError, ConfigFile};
+use bevy::{ecs::message::Message, prelude::*};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Config resources ConfigHotReloadPlugin can reload, implemented by `#[py_bevy_config_res(hot_reload)]`
+pub trait ReloadableConfig: Resource + Serialize + Sized {
+    /// Path in the config's env var
+    fn env_config_path() -> Result<PathBuf, ConfigError>;
+    fn load_config_path(path: &Path) -> Result<Self, ConfigError>;
+}
+
+/// Files a config resource was loaded from, watched by ConfigHotReloadPlugin when it has no path of its own
+///
+/// The loaders of `hot_reload` configs record one with record_config_source, the plugin inserts it on its first poll
+#[derive(Resource)]
+pub struct ConfigSource<T> {
+    /// The files given to the loader, ex: every layer of from_layers
+    pub paths: Vec<PathBuf>,
+    /// Every file read while loading, including the files they extend
+    pub files: Vec<ConfigFile>,
+    load: fn(&[PathBuf]) -> Result<T, ConfigError>,
+}
+
+impl<T> Clone for ConfigSource<T> {
+    fn clone(&self) -> Self {
+        Self {
+            paths: self.paths.clone(),
+            files: self.files.clone(),
+            load: self.load,
+        }
+    }
+}
+
+impl<T: ReloadableConfig> ConfigSource<T> {
+    /// A single file loaded with load_config_path, changes are seen from its current modified time
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let files = vec![(path.clone(), modified_time(&path))];
+        Self::loaded(vec![path], files, |paths| T::load_config_path(&paths[0]))
+    }
+}
+
+impl<T> ConfigSource<T> {
+    /// A config loaded from `paths` with `load`, which read `files` (from track_config_files)
+    pub fn loaded(
+        paths: Vec<PathBuf>,
+        files: Vec<ConfigFile>,
+        load: fn(&[PathBuf]) -> Result<T, ConfigError>,
+    ) -> Self {
+        Self { paths, files, load }
+    }
+
+    // the first file modified since it was read, None while a file is missing (editors can briefly delete it)
+    fn changed_file(&self) -> Option<PathBuf> {
+        let mut changed = None;
+        for (path, loaded) in &self.files {
+            let modified = modified_time(path)?;
+            if changed.is_none() && *loaded != Some(modified) {
+                changed = Some(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+// the source of the last config of each type loaded by its generated loaders
+static RECORDED_SOURCES: Mutex<Vec<(TypeId, Box<dyn Any + Send>)>> = Mutex::new(Vec::new());
+
+/// Remember where the last `T` was loaded from, for ConfigHotReloadPlugin<T> to watch
+pub fn record_config_source<T: 'static>(source: ConfigSource<T>) {
+    let mut sources = RECORDED_SOURCES
+        .lock()
+        .unwrap_or_else(|what| what.into_inner());
+    sources.retain(|(type_id, _)| *type_id != TypeId::of::<T>());
+    sources.push((TypeId::of::<T>(), Box::new(source)));
+}
+
+fn recorded_config_source<T: 'static>() -> Option<ConfigSource<T>> {
+    let sources = RECORDED_SOURCES
+        .lock()
+        .unwrap_or_else(|what| what.into_inner());
+    sources
+        .iter()
+        .find(|(type_id, _)| *type_id == TypeId::of::<T>())
+        .and_then(|(_, source)| source.downcast_ref::<ConfigSource<T>>())
+        .cloned()
+}
+
+/// Written after ConfigHotReloadPlugin replaces the config resource
+pub struct ConfigReloaded<T> {
+    /// The file that changed
+    pub path: PathBuf,
+    /// Dotted paths of the fields that changed, ex: physics.gravity
+    pub changed: Vec<String>,
+    _config: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Message for ConfigReloaded<T> {}
+
+/// Re-parse a config resource when any file it was loaded from changes, polling the files' modified times
+///
+/// Watches the plugin's path, then the ConfigSource<T> resource, then where the config was last loaded from,
+/// then the config's env var path. Invalid files are logged and the current resource is kept
+pub struct ConfigHotReloadPlugin<T> {
+    path: Option<PathBuf>,
+    poll_interval: Duration,
+    _config: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for ConfigHotReloadPlugin<T> {
+    fn default() -> Self {
+        Self {
+            path: None,
+            poll_interval: Duration::from_secs(1),
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<T> ConfigHotReloadPlugin<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+}
+
+impl<T: ReloadableConfig> Plugin for ConfigHotReloadPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ConfigReloaded<T>>()
+            .insert_resource(ConfigWatch::<T> {
+                path: self.path.clone(),
+                poll_interval: self.poll_interval,
+                last_poll: None,
+                warned_no_path: false,
+                _config: PhantomData,
+            })
+            .add_systems(Update, reload_changed_config::<T>);
+    }
+}
+
+#[derive(Resource)]
+struct ConfigWatch<T> {
+    path: Option<PathBuf>,
+    poll_interval: Duration,
+    last_poll: Option<Instant>,
+    warned_no_path: bool,
+    _config: PhantomData<fn() -> T>,
+}
+
+impl<T: ReloadableConfig> ConfigWatch<T> {
+    // where the config comes from, in the plugin's order
+    fn source(&mut self, current: Option<&ConfigSource<T>>) -> Option<ConfigSource<T>> {
+        let loaded_from = |path: &Path| {
+            recorded_config_source::<T>()
+                .filter(|source| source.paths == [path])
+                .unwrap_or_else(|| ConfigSource::new(path))
+        };
+        match (&self.path, current) {
+            (Some(path), Some(current)) if current.paths == [path.as_path()] => {
+                Some(current.clone())
+            }
+            (Some(path), _) => Some(loaded_from(path)),
+            (None, Some(current)) => Some(current.clone()),
+            (None, None) => match recorded_config_source::<T>() {
+                Some(source) => Some(source),
+                None => match T::env_config_path() {
+                    Ok(path) => Some(loaded_from(&path)),
+                    Err(what) => {
+                        if !self.warned_no_path {
+                            warn!("Not hot reloading {}: {}", std::any::type_name::<T>(), what);
+                            self.warned_no_path = true;
+                        }
+                        None
+                    }
+                },
+            },
+        }
+    }
+}
+
+fn reload_changed_config<T: ReloadableConfig>(
+    mut watch: ResMut<ConfigWatch<T>>,
+    current: Option<Res<ConfigSource<T>>>,
+    config: Option<Res<T>>,
+    mut commands: Commands,
+    mut reloaded: MessageWriter<ConfigReloaded<T>>,
+) {
+    if watch
+        .last_poll
+        .is_some_and(|last_poll| last_poll.elapsed() < watch.poll_interval)
+    {
+        return;
+    }
+    watch.last_poll = Some(Instant::now());
+
+    let Some(source) = watch.source(current.as_deref()) else {
+        return;
+    };
+    // a missing resource is loaded right away
+    let changed = match config {
+        Some(_) => source.changed_file(),
+        None => source.paths.first().cloned(),
+    };
+    let Some(path) = changed else {
+        commands.insert_resource(source);
+        return;
+    };
+
+    let (new_config, files) = track_config_files(|| (source.load)(&source.paths));
+    commands.insert_resource(ConfigSource::loaded(
+        source.paths.clone(),
+        files,
+        source.load,
+    ));
+    let new_config = match new_config {
+        Ok(new_config) => new_config,
+        Err(what) => {
+            error!("Failed to reload {}: {}", std::any::type_name::<T>(), what);
+            return;
+        }
+    };
+
+    let mut changes = Vec::new();
+    if let Some(config) = config {
+        let old = serde_yaml::to_value(&*config).unwrap_or(Value::Null);
+        let new = serde_yaml::to_value(&new_config).unwrap_or(Value::Null);
+        diff_values("", &old, &new, &mut changes);
+    }
+
+    info!("Reloaded {} from >> {:?}", std::any::type_name::<T>(), path);
+    for (field, old, new) in &changes {
+        info!("    {}: {} -> {}", field, old, new);
+    }
+
+    commands.insert_resource(new_config);
+    reloaded.write(ConfigReloaded {
+        path,
+        changed: changes.into_iter().map(|(field, _, _)| field).collect(),
+        _config: PhantomData,
+    });
+}
+
+// (dotted field path, old value, new value) of every leaf that differs
+fn diff_values(
+    prefix: &str,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<(String, String, String)>,
+) {
+    match (old, new) {
+        (Value::Mapping(old_map), Value::Mapping(new_map)) => {
+            let keys = old_map
+                .keys()
+                .chain(new_map.keys().filter(|key| !old_map.contains_key(*key)));
+            for key in keys {
+                let key_str = match key {
+                    Value::String(key) => key.clone(),
+                    key => show_value(key),
+                };
+                let field = match prefix {
+                    "" => key_str,
+                    prefix => format!("{}.{}", prefix, key_str),
+                };
+                let null = Value::Null;
+                diff_values(
+                    &field,
+                    old_map.get(key).unwrap_or(&null),
+                    new_map.get(key).unwrap_or(&null),
+                    changes,
+                );
+            }
+        }
+        (old, new) if old != new => {
+            changes.push((prefix.to_string(), show_value(old), show_value(new)));
+        }
+        _ => {}
+    }
+}
+
+fn show_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        value => serde_yaml::to_string(value)
+            .map(|yaml| yaml.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,494 @@
+use crate::{load_config_value, ConfigError, ConfigFormat};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+/// Types with a JSON Schema, structs and unit enums can `#[derive(ConfigSchema)]`
+pub trait ConfigSchema {
+    fn config_schema() -> Value;
+}
+
+/// Schema of a config file, with the `$schema` dialect and title set
+pub fn root_config_schema<T: ConfigSchema>(title: &str) -> Value {
+    let mut schema = T::config_schema();
+    if let Value::Mapping(schema) = &mut schema {
+        schema.insert(
+            "$schema".into(),
+            "https://json-schema.org/draft/2020-12/schema".into(),
+        );
+        schema.insert("title".into(), title.into());
+    }
+    schema
+}
+
+/// Serialized `T::default()`, used for the `default` of fields
+pub fn config_schema_default<T: Serialize + Default>() -> Value {
+    serde_yaml::to_value(T::default()).unwrap_or(Value::Null)
+}
+
+fn type_schema(ty: &str) -> Value {
+    let mut schema = Mapping::new();
+    schema.insert("type".into(), ty.into());
+    Value::Mapping(schema)
+}
+
+macro_rules! impl_config_schema {
+    ($schema:expr => $($ty:ty),* $(,)?) => {
+        $(impl ConfigSchema for $ty {
+            fn config_schema() -> Value {
+                $schema
+            }
+        })*
+    };
+}
+
+impl_config_schema!(type_schema("boolean") => bool);
+impl_config_schema!(type_schema("integer") => i8, i16, i32, i64, i128, isize);
+impl_config_schema!(unsigned_schema() => u8, u16, u32, u64, u128, usize);
+impl_config_schema!(type_schema("number") => f32, f64);
+impl_config_schema!(type_schema("string") => String, char, std::path::PathBuf);
+
+fn unsigned_schema() -> Value {
+    let mut schema = type_schema("integer");
+    schema["minimum"] = 0.into();
+    schema
+}
+
+fn array_schema(items: Value, len: Option<usize>) -> Value {
+    let mut schema = type_schema("array");
+    schema["items"] = items;
+    if let Some(len) = len {
+        schema["minItems"] = len.into();
+        schema["maxItems"] = len.into();
+    }
+    schema
+}
+
+fn map_schema(values: Value) -> Value {
+    let mut schema = type_schema("object");
+    schema["additionalProperties"] = values;
+    schema
+}
+
+impl<T: ConfigSchema> ConfigSchema for Option<T> {
+    fn config_schema() -> Value {
+        let mut schema = Mapping::new();
+        schema.insert(
+            "anyOf".into(),
+            Value::Sequence(vec![T::config_schema(), type_schema("null")]),
+        );
+        Value::Mapping(schema)
+    }
+}
+impl<T: ConfigSchema> ConfigSchema for Box<T> {
+    fn config_schema() -> Value {
+        T::config_schema()
+    }
+}
+impl<T: ConfigSchema> ConfigSchema for Vec<T> {
+    fn config_schema() -> Value {
+        array_schema(T::config_schema(), None)
+    }
+}
+impl<T: ConfigSchema, const N: usize> ConfigSchema for [T; N] {
+    fn config_schema() -> Value {
+        array_schema(T::config_schema(), Some(N))
+    }
+}
+impl<T: ConfigSchema, S> ConfigSchema for std::collections::HashSet<T, S> {
+    fn config_schema() -> Value {
+        array_schema(T::config_schema(), None)
+    }
+}
+impl<T: ConfigSchema, S> ConfigSchema for std::collections::HashMap<String, T, S> {
+    fn config_schema() -> Value {
+        map_schema(T::config_schema())
+    }
+}
+impl<T: ConfigSchema> ConfigSchema for std::collections::BTreeMap<String, T> {
+    fn config_schema() -> Value {
+        map_schema(T::config_schema())
+    }
+}
+
+// glam serializes vectors as fixed length lists
+#[cfg(feature = "bevy")]
+impl_config_schema!(array_schema(type_schema("number"), Some(2)) => bevy::math::Vec2, bevy::math::DVec2);
+#[cfg(feature = "bevy")]
+impl_config_schema!(array_schema(type_schema("number"), Some(3)) => bevy::math::Vec3, bevy::math::Vec3A, bevy::math::DVec3);
+#[cfg(feature = "bevy")]
+impl_config_schema!(array_schema(type_schema("number"), Some(4)) => bevy::math::Vec4, bevy::math::DVec4, bevy::math::Quat);
+#[cfg(feature = "bevy")]
+impl_config_schema!(array_schema(type_schema("integer"), Some(2)) => bevy::math::IVec2);
+#[cfg(feature = "bevy")]
+impl_config_schema!(array_schema(type_schema("integer"), Some(3)) => bevy::math::IVec3);
+#[cfg(feature = "bevy")]
+impl_config_schema!(array_schema(unsigned_schema(), Some(2)) => bevy::math::UVec2);
+#[cfg(feature = "bevy")]
+impl_config_schema!(array_schema(unsigned_schema(), Some(3)) => bevy::math::UVec3);
+
+/// Every place `value` doesn't match `schema`, as `field.path: problem`
+///
+/// Supports the subset of JSON Schema that ConfigSchema generates
+pub fn validate_config_schema(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate(schema, value, "", &mut errors);
+    errors
+}
+
+fn validate(schema: &Value, value: &Value, at: &str, errors: &mut Vec<String>) {
+    let location = match at {
+        "" => "<root>",
+        at => at,
+    };
+
+    if let Some(Value::Sequence(options)) = schema.get("anyOf") {
+        let matches = |option| {
+            let mut option_errors = Vec::new();
+            validate(option, value, at, &mut option_errors);
+            option_errors.is_empty()
+        };
+        if !options.iter().any(matches) {
+            // report against the first non null option, which is the one users usually mean
+            match options
+                .iter()
+                .find(|option| option.get("type") != Some(&"null".into()))
+            {
+                Some(option) => validate(option, value, at, errors),
+                None => errors.push(format!("{}: doesn't match any allowed type", location)),
+            }
+        }
+        return;
+    }
+
+    if let Some(Value::Sequence(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let allowed: Vec<_> = allowed.iter().map(show_value).collect();
+            errors.push(format!(
+                "{}: expected one of {}, got {}",
+                location,
+                allowed.join(", "),
+                show_value(value)
+            ));
+        }
+        return;
+    }
+
+    let Some(Value::String(ty)) = schema.get("type") else {
+        return;
+    };
+    let type_ok = match ty.as_str() {
+        "null" => value.is_null(),
+        "boolean" => value.is_bool(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_sequence(),
+        "object" => value.is_mapping(),
+        _ => true,
+    };
+    if !type_ok {
+        errors.push(format!(
+            "{}: expected {}, got {}",
+            location,
+            ty,
+            show_value(value)
+        ));
+        return;
+    }
+
+    if let (Some(minimum), Some(number)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if number < minimum {
+            errors.push(format!("{}: {} is less than {}", location, number, minimum));
+        }
+    }
+
+    match value {
+        Value::Sequence(items) => {
+            let len = items.len() as u64;
+            let min = schema.get("minItems").and_then(Value::as_u64);
+            let max = schema.get("maxItems").and_then(Value::as_u64);
+            if min.is_some_and(|min| len < min) || max.is_some_and(|max| len > max) {
+                errors.push(format!(
+                    "{}: expected {} items, got {}",
+                    location,
+                    min.or(max).unwrap_or_default(),
+                    len
+                ));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate(item_schema, item, &format!("{}[{}]", at, i), errors);
+                }
+            }
+        }
+        Value::Mapping(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_mapping);
+            if let Some(Value::Sequence(required)) = schema.get("required") {
+                for field in required {
+                    if !fields.contains_key(field) {
+                        errors.push(format!("{}: missing field {}", location, show_value(field)));
+                    }
+                }
+            }
+            for (key, field_value) in fields {
+                let field_at = match at {
+                    "" => show_value(key),
+                    at => format!("{}.{}", at, show_value(key)),
+                };
+                match (
+                    properties.and_then(|props| props.get(key)),
+                    schema.get("additionalProperties"),
+                ) {
+                    (Some(field_schema), _) => {
+                        validate(field_schema, field_value, &field_at, errors)
+                    }
+                    (None, Some(Value::Bool(false))) => {
+                        errors.push(format!("{}: unknown field", field_at))
+                    }
+                    (None, Some(extra_schema)) if extra_schema.is_mapping() => {
+                        validate(extra_schema, field_value, &field_at, errors)
+                    }
+                    (None, _) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn show_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "null".to_string(),
+        value => serde_yaml::to_string(value)
+            .map(|yaml| yaml.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Load a config file like load_config, reporting every mismatch with `schema` at once
+pub fn load_config_checked<T: DeserializeOwned>(
+    path: &Path,
+    format: ConfigFormat,
+    schema: &Value,
+) -> Result<T, ConfigError> {
+    let value = load_config_value(path, format)?;
+    let errors = validate_config_schema(schema, &value);
+    if !errors.is_empty() {
+        return Err(ConfigError::Invalid {
+            path: path.to_path_buf(),
+            errors,
+        });
+    }
+    serde_yaml::from_value(value).map_err(|what| ConfigError::Parse {
+        path: path.to_path_buf(),
+        line: None,
+        column: None,
+        message: what.to_string(),
+    })
+}
+
+/// Convert a config value (ex: a schema) into the matching python object
+#[cfg(feature = "minimal-pyo3")]
+pub fn config_value_to_py(
+    py: pyo3::Python<'_>,
+    value: &Value,
+) -> pyo3::PyResult<pyo3::Py<pyo3::PyAny>> {
+    use pyo3::{prelude::*, types::PyDict, IntoPyObjectExt};
+
+    match value {
+        Value::Null => Ok(py.None()),
+        Value::Bool(b) => b.into_py_any(py),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_py_any(py),
+            (None, Some(u)) => u.into_py_any(py),
+            _ => n.as_f64().unwrap_or(f64::NAN).into_py_any(py),
+        },
+        Value::String(s) => s.into_py_any(py),
+        Value::Sequence(items) => items
+            .iter()
+            .map(|item| config_value_to_py(py, item))
+            .collect::<PyResult<Vec<_>>>()?
+            .into_py_any(py),
+        Value::Mapping(fields) => {
+            let dict = PyDict::new(py);
+            for (key, val) in fields {
+                dict.set_item(config_value_to_py(py, key)?, config_value_to_py(py, val)?)?;
+            }
+            dict.into_py_any(py)
+        }
+        Value::Tagged(tagged) => config_value_to_py(py, &tagged.value),
+    }
+}
+
+/// A struct field in a generated schema
+pub struct SchemaField {
+    pub name: &'static str,
+    pub schema: Value,
+    pub description: Option<&'static str>,
+    pub default: Option<Value>,
+    pub required: bool,
+    /// `#[serde(flatten)]`, the field's properties are merged into the struct
+    pub flatten: bool,
+}
+
+/// Schema of a struct, unknown fields are errors
+pub fn struct_config_schema(description: Option<&str>, fields: Vec<SchemaField>) -> Value {
+    let mut properties = Mapping::new();
+    let mut required = Vec::new();
+    for field in fields {
+        if field.flatten {
+            if let Some(Value::Mapping(flat)) = field.schema.get("properties") {
+                properties.extend(flat.clone());
+            }
+            if let Some(Value::Sequence(flat)) = field.schema.get("required") {
+                required.extend(flat.iter().cloned());
+            }
+            continue;
+        }
+
+        let mut schema = field.schema;
+        if let Value::Mapping(schema) = &mut schema {
+            if let Some(description) = field.description {
+                schema.insert("description".into(), description.into());
+            }
+            if let Some(default) = field.default {
+                schema.insert("default".into(), default);
+            }
+        }
+        if field.required {
+            required.push(field.name.into());
+        }
+        properties.insert(field.name.into(), schema);
+    }
+
+    let mut schema = type_schema("object");
+    schema["properties"] = Value::Mapping(properties);
+    schema["required"] = Value::Sequence(required);
+    schema["additionalProperties"] = false.into();
+    describe(schema, description)
+}
+
+/// Schema of a unit enum, serialized as one of the variant names
+pub fn enum_config_schema(description: Option<&str>, variants: &[&str]) -> Value {
+    let mut schema = type_schema("string");
+    schema["enum"] = Value::Sequence(variants.iter().map(|variant| (*variant).into()).collect());
+    describe(schema, description)
+}
+
+/// Schema of a newtype, the same as its inner type
+pub fn newtype_config_schema(description: Option<&str>, inner: Value) -> Value {
+    describe(inner, description)
+}
+
+fn describe(mut schema: Value, description: Option<&str>) -> Value {
+    if let (Value::Mapping(schema), Some(description)) = (&mut schema, description) {
+        schema.insert("description".into(), description.into());
+    }
+    schema
+}
+
+/// Serialize a field's `#[serde(default = "...")]` value
+pub fn config_schema_value<T: Serialize>(value: &T) -> Value {
+    serde_yaml::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Write a schema as pretty json
+#[cfg(feature = "json")]
+pub fn write_config_schema(path: &Path, schema: &Value) -> Result<(), ConfigError> {
+    let contents = ConfigFormat::Json.serialize(path, schema)?;
+    std::fs::write(path, contents).map_err(|source| ConfigError::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Every mismatch between a config file (and what it extends) and `schema`
+pub fn validate_config_path(
+    path: &Path,
+    format: ConfigFormat,
+    schema: &Value,
+) -> Result<Vec<String>, ConfigError> {
+    let value = load_config_value(path, format)?;
+    Ok(validate_config_schema(schema, &value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &'static str, schema: Value, required: bool) -> SchemaField {
+        SchemaField {
+            name,
+            schema,
+            description: None,
+            default: None,
+            required,
+            flatten: false,
+        }
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let physics = struct_config_schema(
+            None,
+            vec![
+                field("gravity", f64::config_schema(), true),
+                field("steps", u32::config_schema(), true),
+            ],
+        );
+        let schema = struct_config_schema(
+            None,
+            vec![
+                field("name", String::config_schema(), true),
+                field("physics", physics, true),
+                field("layers", Vec::<u32>::config_schema(), false),
+                field("mode", enum_config_schema(None, &["fast", "slow"]), false),
+            ],
+        );
+        let value: Value = serde_yaml::from_str(
+            "
+            physics:
+              gravity: down
+              steps: -1
+              drag: 0.1
+            layers: [1, two, 3]
+            mode: medium
+            colour: red
+            ",
+        )
+        .unwrap();
+
+        let mut errors = validate_config_schema(&schema, &value);
+        errors.sort();
+        assert_eq!(
+            errors,
+            [
+                "<root>: missing field name",
+                "colour: unknown field",
+                "layers[1]: expected integer, got two",
+                "mode: expected one of fast, slow, got medium",
+                "physics.drag: unknown field",
+                "physics.gravity: expected number, got down",
+                "physics.steps: -1 is less than 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_document_has_no_errors() {
+        let schema = struct_config_schema(
+            None,
+            vec![
+                field("name", String::config_schema(), true),
+                field("scale", Option::<f32>::config_schema(), false),
+            ],
+        );
+        let value: Value = serde_yaml::from_str("name: app\nscale: null").unwrap();
+        assert!(validate_config_schema(&schema, &value).is_empty());
+    }
+}
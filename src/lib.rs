@@ -1,15 +1,63 @@
 #[cfg(feature = "bevy")]
+mod arrow;
+#[cfg(feature = "bevy")]
+mod columns;
+mod config;
+#[cfg(feature = "yaml")]
+mod config_layers;
+#[cfg(all(feature = "bevy", feature = "yaml"))]
+mod config_reload;
+#[cfg(feature = "yaml")]
+mod config_schema;
+#[cfg(feature = "minimal-pyo3")]
+mod numpy_view;
+#[cfg(feature = "bevy")]
 mod plugin;
 #[cfg(feature = "bevy")]
 mod registry;
+#[cfg(feature = "gen-ref-stubs")]
+mod stubs;
 #[cfg(feature = "bevy")]
 mod world_ref;
 
 // public re-exports
 #[cfg(feature = "bevy")]
+pub use arrow::{column_to_arrow, ArrowColumn, ArrowElement, ArrowRecordBatch};
+#[cfg(feature = "bevy")]
+pub use columns::{
+    column_to_numpy, column_writer, BevyPyColumns, ColumnField, ColumnWriter, ComponentColumns,
+};
+pub use config::{
+    load_config, save_config, track_config_files, ConfigError, ConfigFile, ConfigFormat,
+};
+#[cfg(all(feature = "yaml", feature = "minimal-pyo3"))]
+pub use config_layers::py_to_config_value;
+#[cfg(feature = "yaml")]
+pub use config_layers::{load_config_value, ConfigLayers};
+#[cfg(all(feature = "yaml", feature = "minimal-pyo3"))]
+pub use config_schema::config_value_to_py;
+#[cfg(feature = "yaml")]
+pub use config_schema::{
+    config_schema_default, config_schema_value, enum_config_schema, load_config_checked,
+    newtype_config_schema, root_config_schema, struct_config_schema, validate_config_path,
+    validate_config_schema, ConfigSchema, SchemaField,
+};
+#[cfg(all(feature = "yaml", feature = "json"))]
+pub use config_schema::write_config_schema;
+#[cfg(feature = "yaml")]
+pub use serde_yaml::Value as ConfigValue;
+#[cfg(all(feature = "bevy", feature = "yaml"))]
+pub use config_reload::{
+    record_config_source, ConfigHotReloadPlugin, ConfigReloaded, ConfigSource, ReloadableConfig,
+};
+#[cfg(feature = "minimal-pyo3")]
+pub use numpy_view::{ensure_not_exported, AsBufferSlice, BufferElement, BufferView};
+#[cfg(feature = "bevy")]
 pub use plugin::PyBevyPlugin;
 #[cfg(feature = "bevy")]
 pub use registry::PyObjectRegistry;
+#[cfg(feature = "gen-ref-stubs")]
+pub use stubs::stub_type_of;
 #[cfg(feature = "bevy")]
 pub use world_ref::{BevyHealthCheckPtr, UnsafeWorldRef};
 
@@ -20,6 +68,56 @@ pub use bevy::prelude::*;
 
 pub trait MakePathsAbsolute {
     fn make_paths_absolute(&mut self, _parent_path: &std::path::PathBuf) {}
+    /// Undo make_paths_absolute before saving, paths outside the parent stay absolute
+    fn make_paths_relative(&mut self, _parent_path: &std::path::PathBuf) {}
+}
+
+// Relative paths are joined onto the parent, absolute paths are left as is
+impl MakePathsAbsolute for std::path::PathBuf {
+    fn make_paths_absolute(&mut self, parent_path: &std::path::PathBuf) {
+        *self = parent_path.join(&*self);
+    }
+    fn make_paths_relative(&mut self, parent_path: &std::path::PathBuf) {
+        let relative = self.strip_prefix(parent_path).or_else(|_| {
+            let parent_path = std::path::absolute(parent_path).unwrap_or_default();
+            self.strip_prefix(parent_path)
+        });
+        if let Ok(relative) = relative {
+            *self = relative.to_path_buf();
+        }
+    }
+}
+impl<T: MakePathsAbsolute> MakePathsAbsolute for Option<T> {
+    fn make_paths_absolute(&mut self, parent_path: &std::path::PathBuf) {
+        if let Some(val) = self {
+            val.make_paths_absolute(parent_path);
+        }
+    }
+    fn make_paths_relative(&mut self, parent_path: &std::path::PathBuf) {
+        if let Some(val) = self {
+            val.make_paths_relative(parent_path);
+        }
+    }
+}
+impl<T: MakePathsAbsolute> MakePathsAbsolute for Vec<T> {
+    fn make_paths_absolute(&mut self, parent_path: &std::path::PathBuf) {
+        for val in self {
+            val.make_paths_absolute(parent_path);
+        }
+    }
+    fn make_paths_relative(&mut self, parent_path: &std::path::PathBuf) {
+        for val in self {
+            val.make_paths_relative(parent_path);
+        }
+    }
+}
+impl<T: MakePathsAbsolute> MakePathsAbsolute for Box<T> {
+    fn make_paths_absolute(&mut self, parent_path: &std::path::PathBuf) {
+        (**self).make_paths_absolute(parent_path);
+    }
+    fn make_paths_relative(&mut self, parent_path: &std::path::PathBuf) {
+        (**self).make_paths_relative(parent_path);
+    }
 }
 
 #[cfg(feature = "py-ref")]
@@ -36,6 +134,62 @@ mod ref_traits {
 #[cfg(feature = "py-ref")]
 pub use ref_traits::*;
 
+#[cfg(feature = "minimal-pyo3")]
+mod owned_or_ref {
+    use pyo3::{conversion::FromPyObjectOwned, prelude::*};
+    use std::marker::PhantomData;
+
+    /// Extract an owned value from python, either from the owned class or any of its reference classes
+    ///
+    /// Reference classes are converted through their `to_owned` method.
+    /// `R` is the reference class expected to be passed, only used for stubs
+    pub struct OwnedOrRef<T, R = T>(pub T, PhantomData<R>);
+
+    impl<'a, 'py, T: FromPyObjectOwned<'py>, R> FromPyObject<'a, 'py> for OwnedOrRef<T, R> {
+        type Error = PyErr;
+
+        fn extract(obj: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+            let owned = match obj.extract::<T>() {
+                Ok(owned) => owned,
+                Err(err) => match obj.call_method0("to_owned") {
+                    Ok(owned) => owned.extract::<T>().map_err(Into::into)?,
+                    Err(_) => return Err(err.into()),
+                },
+            };
+            Ok(Self(owned, PhantomData))
+        }
+    }
+
+    #[cfg(feature = "gen-ref-stubs")]
+    impl<T: pyo3_stub_gen::PyStubType, R: pyo3_stub_gen::PyStubType> pyo3_stub_gen::PyStubType
+        for OwnedOrRef<T, R>
+    {
+        fn type_output() -> pyo3_stub_gen::TypeInfo {
+            T::type_output()
+        }
+        fn type_input() -> pyo3_stub_gen::TypeInfo {
+            T::type_input() | R::type_input()
+        }
+    }
+}
+#[cfg(feature = "minimal-pyo3")]
+pub use owned_or_ref::*;
+
+#[cfg(feature = "minimal-pyo3")]
+mod py_dict {
+    use pyo3::{prelude::*, types::PyDict};
+
+    /// Convert a struct to and from a python dict keyed by its python field names
+    ///
+    /// Generated by the reference derives with `#[py_bevy(dict)]`
+    pub trait PyDictConvert: Sized {
+        fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>>;
+        fn from_py_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self>;
+    }
+}
+#[cfg(feature = "minimal-pyo3")]
+pub use py_dict::*;
+
 pub trait UnwrapOrFromYamlEnv<T> {
     fn unwrap_or_from_yaml_env(self) -> Result<T, Box<dyn std::error::Error>>;
 }
@@ -218,3 +372,55 @@ pub use pyo3_traits::*;
 //
 //     Ok(())
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::MakePathsAbsolute;
+    use std::path::PathBuf;
+
+    fn round_trip<T: MakePathsAbsolute + Clone + PartialEq + std::fmt::Debug>(
+        value: T,
+        absolute: T,
+    ) {
+        let parent = PathBuf::from("/configs/app");
+        let mut converted = value.clone();
+        converted.make_paths_absolute(&parent);
+        assert_eq!(converted, absolute);
+        converted.make_paths_relative(&parent);
+        assert_eq!(converted, value);
+    }
+
+    #[test]
+    fn paths_round_trip() {
+        round_trip(
+            PathBuf::from("data/map.png"),
+            PathBuf::from("/configs/app/data/map.png"),
+        );
+        round_trip(
+            Some(PathBuf::from("map.png")),
+            Some(PathBuf::from("/configs/app/map.png")),
+        );
+        round_trip(None::<PathBuf>, None);
+        round_trip(
+            vec![PathBuf::from("a.png"), PathBuf::from("b/c.png")],
+            vec![
+                PathBuf::from("/configs/app/a.png"),
+                PathBuf::from("/configs/app/b/c.png"),
+            ],
+        );
+        round_trip(
+            Box::new(vec![Some(PathBuf::from("map.png"))]),
+            Box::new(vec![Some(PathBuf::from("/configs/app/map.png"))]),
+        );
+    }
+
+    #[test]
+    fn paths_outside_parent_stay_absolute() {
+        let parent = PathBuf::from("/configs/app");
+        let mut path = PathBuf::from("/data/map.png");
+        path.make_paths_absolute(&parent);
+        assert_eq!(path, PathBuf::from("/data/map.png"));
+        path.make_paths_relative(&parent);
+        assert_eq!(path, PathBuf::from("/data/map.png"));
+    }
+}
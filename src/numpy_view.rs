@@ -0,0 +1,362 @@
+use pyo3::{
+    exceptions::{PyBufferError, PyValueError},
+    ffi,
+    prelude::*,
+};
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    ffi::{c_int, c_void, CStr},
+    sync::Mutex,
+};
+
+/// Element types that can be shared with python through the buffer protocol
+///
+/// # Safety
+/// `FORMAT` must be the struct module format character matching the layout of `Self`
+pub unsafe trait BufferElement: Copy + Default + 'static {
+    const FORMAT: &'static CStr;
+    /// Whether python may write any bytes of `Self`'s size into the buffer, views are read only otherwise
+    const WRITABLE: bool = true;
+}
+
+macro_rules! impl_buffer_element {
+    ($($ty:ty => $format:literal),* $(,)?) => {
+        $(unsafe impl BufferElement for $ty {
+            const FORMAT: &'static CStr = $format;
+        })*
+    };
+}
+
+// python can write bytes other than 0 and 1 into a bool buffer, so bool views are always read only
+unsafe impl BufferElement for bool {
+    const FORMAT: &'static CStr = c"?";
+    const WRITABLE: bool = false;
+}
+
+impl_buffer_element!(
+    i8 => c"b",
+    u8 => c"B",
+    i16 => c"h",
+    u16 => c"H",
+    i32 => c"i",
+    u32 => c"I",
+    i64 => c"q",
+    u64 => c"Q",
+    f32 => c"f",
+    f64 => c"d",
+);
+
+/// Fields stored as a contiguous run of BufferElements, which can be viewed as a numpy array without copying
+pub trait AsBufferSlice: Sized {
+    type Element: BufferElement;
+
+    fn as_buffer_slice(&self) -> &[Self::Element];
+    fn as_buffer_slice_mut(&mut self) -> &mut [Self::Element];
+    /// Build the field from python's elements, fixed size fields need the exact length
+    fn from_buffer_elements(values: Vec<Self::Element>) -> PyResult<Self>;
+}
+
+fn fixed_len_elements<T, const N: usize>(values: Vec<T>) -> PyResult<[T; N]> {
+    let len = values.len();
+    values
+        .try_into()
+        .map_err(|_| PyValueError::new_err(format!("Expected {} elements, got {}", N, len)))
+}
+
+impl<T: BufferElement> AsBufferSlice for Vec<T> {
+    type Element = T;
+
+    fn as_buffer_slice(&self) -> &[T] {
+        self
+    }
+    fn as_buffer_slice_mut(&mut self) -> &mut [T] {
+        self
+    }
+    fn from_buffer_elements(values: Vec<T>) -> PyResult<Self> {
+        Ok(values)
+    }
+}
+
+impl<T: BufferElement, const N: usize> AsBufferSlice for [T; N] {
+    type Element = T;
+
+    fn as_buffer_slice(&self) -> &[T] {
+        self
+    }
+    fn as_buffer_slice_mut(&mut self) -> &mut [T] {
+        self
+    }
+    fn from_buffer_elements(values: Vec<T>) -> PyResult<Self> {
+        fixed_len_elements(values)
+    }
+}
+
+#[cfg(feature = "bevy")]
+macro_rules! impl_as_buffer_slice_glam {
+    ($($ty:ty => [$elem:ty; $n:literal]),* $(,)?) => {
+        $(impl AsBufferSlice for $ty {
+            type Element = $elem;
+
+            fn as_buffer_slice(&self) -> &[$elem] {
+                AsRef::<[$elem; $n]>::as_ref(self)
+            }
+            fn as_buffer_slice_mut(&mut self) -> &mut [$elem] {
+                AsMut::<[$elem; $n]>::as_mut(self)
+            }
+            fn from_buffer_elements(values: Vec<$elem>) -> PyResult<Self> {
+                fixed_len_elements::<$elem, $n>(values).map(Self::from)
+            }
+        })*
+    };
+}
+
+#[cfg(feature = "bevy")]
+impl_as_buffer_slice_glam!(
+    bevy::math::Vec2 => [f32; 2],
+    bevy::math::Vec3 => [f32; 3],
+    bevy::math::Vec3A => [f32; 3],
+    bevy::math::Vec4 => [f32; 4],
+    bevy::math::DVec2 => [f64; 2],
+    bevy::math::DVec3 => [f64; 3],
+    bevy::math::DVec4 => [f64; 4],
+    bevy::math::IVec2 => [i32; 2],
+    bevy::math::IVec3 => [i32; 3],
+    bevy::math::IVec4 => [i32; 4],
+    bevy::math::UVec2 => [u32; 2],
+    bevy::math::UVec3 => [u32; 3],
+    bevy::math::UVec4 => [u32; 4],
+);
+
+// Live exports of every buffer by address, so a field can't be replaced while python views it
+static EXPORTED_BUFFERS: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+fn count_export(buf: *mut c_void, len: isize, change: isize) {
+    // empty buffers share a dangling address and have nothing to free
+    if len == 0 {
+        return;
+    }
+    let mut exported = EXPORTED_BUFFERS.lock().unwrap();
+    let count = exported.entry(buf as usize).or_default();
+    *count = count.saturating_add_signed(change);
+    if *count == 0 {
+        exported.remove(&(buf as usize));
+    }
+}
+
+/// Error if python still has arrays viewing `data`, call before replacing or resizing data that was exported
+pub fn ensure_not_exported<T>(data: &[T]) -> PyResult<()> {
+    let exported = EXPORTED_BUFFERS.lock().unwrap();
+    match !data.is_empty() && exported.contains_key(&(data.as_ptr() as usize)) {
+        true => Err(PyBufferError::new_err(
+            "Field is viewed by numpy arrays, delete them before setting it",
+        )),
+        false => Ok(()),
+    }
+}
+
+type ResolveFn = Box<dyn Fn(Python<'_>) -> PyResult<(*mut c_void, usize)>>;
+
+/// A buffer protocol export of memory owned by rust, used to build numpy arrays
+///
+/// Views made from slices don't own the memory, arrays made from them must not outlive the data or be used
+/// after it's moved, ex: a Vec reallocating or a bevy component being removed.
+/// Views made with `from_field` find the data again on every export
+#[pyclass(unsendable)]
+pub struct BufferView {
+    buf: *mut c_void,
+    shape: Vec<isize>,
+    strides: Vec<isize>,
+    itemsize: isize,
+    format: &'static CStr,
+    readonly: bool,
+    // finds the data on every export, ex: a component in a world that may have been deleted
+    resolve: Option<ResolveFn>,
+    // buffers exported from this view that python hasn't released yet
+    exports: Cell<usize>,
+    // keeps owned data alive for as long as python holds the view
+    _owner: Option<Box<dyn std::any::Any>>,
+}
+
+impl BufferView {
+    /// Read only view of `data`
+    ///
+    /// # Safety
+    /// `data` must stay valid and in place for as long as the view and any buffers exported from it exist
+    pub unsafe fn from_slice<T: BufferElement>(data: &[T]) -> Self {
+        let itemsize = std::mem::size_of::<T>() as isize;
+        Self {
+            buf: data.as_ptr() as *mut c_void,
+            shape: vec![data.len() as isize],
+            strides: vec![itemsize],
+            itemsize,
+            format: T::FORMAT,
+            readonly: true,
+            resolve: None,
+            exports: Cell::new(0),
+            _owner: None,
+        }
+    }
+
+    /// Writable view of `data`, read only for elements that aren't `WRITABLE`
+    ///
+    /// # Safety
+    /// `data` must stay valid and in place for as long as the view and any buffers exported from it exist
+    pub unsafe fn from_slice_mut<T: BufferElement>(data: &mut [T]) -> Self {
+        Self {
+            readonly: !T::WRITABLE,
+            ..unsafe { Self::from_slice(data) }
+        }
+    }
+
+    /// View of the slice `resolve` returns, called again before every export so the view follows the data
+    /// and errors once it's gone. The export fails if the data moved while earlier exports are still alive
+    ///
+    /// # Safety
+    /// The slice must stay valid and in place while buffers exported from it exist,
+    /// code replacing it should check `ensure_not_exported` first. Writable views must point to mutable data
+    pub unsafe fn from_field<T: BufferElement>(
+        resolve: impl Fn(Python<'_>) -> PyResult<*mut [T]> + 'static,
+        readonly: bool,
+        py: Python<'_>,
+    ) -> PyResult<Self> {
+        let data = resolve(py)?;
+        let view = Self {
+            readonly: readonly || !T::WRITABLE,
+            ..unsafe { Self::from_slice(&*data) }
+        };
+        let resolve: ResolveFn = Box::new(move |py| {
+            let data = resolve(py)?;
+            Ok((data as *mut c_void, data.len()))
+        });
+        Ok(Self {
+            resolve: Some(resolve),
+            ..view
+        })
+    }
+
+    /// Writable view owning `data`, laid out row major in `shape`
+    pub fn from_vec<T: BufferElement>(mut data: Vec<T>, shape: &[usize]) -> PyResult<Self> {
+        if shape.iter().product::<usize>() != data.len() {
+            return Err(PyValueError::new_err(format!(
+                "Shape {:?} doesn't match {} elements",
+                shape,
+                data.len()
+            )));
+        }
+
+        let itemsize = std::mem::size_of::<T>() as isize;
+        let mut strides = vec![itemsize; shape.len()];
+        for i in (0..shape.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * shape[i + 1] as isize;
+        }
+
+        // moving the Vec into the box doesn't move its heap allocation
+        let buf = data.as_mut_ptr() as *mut c_void;
+        Ok(Self {
+            buf,
+            shape: shape.iter().map(|dim| *dim as isize).collect(),
+            strides,
+            itemsize,
+            format: T::FORMAT,
+            readonly: !T::WRITABLE,
+            resolve: None,
+            exports: Cell::new(0),
+            _owner: Some(Box::new(data)),
+        })
+    }
+
+    /// Wrap the view in a numpy array, numpy must be installed
+    pub fn into_numpy(self, py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+        let view = Bound::new(py, self)?;
+        py.import("numpy")?.call_method1("asarray", (view,))
+    }
+
+    fn len(&self) -> isize {
+        self.shape.iter().product::<isize>() * self.itemsize
+    }
+}
+
+#[pymethods]
+impl BufferView {
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+
+        // buffer consumers expect BufferError, ex: once the world is deleted
+        let resolved = match &slf.borrow().resolve {
+            Some(resolve) => Some(resolve(slf.py()).map_err(|err| {
+                let buffer_err = PyBufferError::new_err(err.to_string());
+                buffer_err.set_cause(slf.py(), Some(err));
+                buffer_err
+            })?),
+            None => None,
+        };
+        let mut this = slf.borrow_mut();
+        if let Some((buf, len)) = resolved {
+            if (buf, len as isize) != (this.buf, this.shape[0]) {
+                // earlier exports still point at the old shape and data
+                if this.exports.get() > 0 {
+                    return Err(PyBufferError::new_err(
+                        "Field moved while numpy arrays still view it",
+                    ));
+                }
+                this.buf = buf;
+                this.shape[0] = len as isize;
+            }
+        }
+        if this.readonly && (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("Object is not writable"));
+        }
+        if this.shape.len() > 1 && (flags & ffi::PyBUF_ND) != ffi::PyBUF_ND {
+            return Err(PyBufferError::new_err(
+                "Multi dimensional views need the buffer's shape",
+            ));
+        }
+
+        // shape and strides point into the pyclass, which stays alive through view.obj
+        let shape = this.shape.as_ptr() as *mut isize;
+        let strides = this.strides.as_ptr() as *mut isize;
+
+        unsafe {
+            (*view).buf = this.buf;
+            (*view).len = this.len();
+            (*view).readonly = this.readonly as c_int;
+            (*view).itemsize = this.itemsize;
+            (*view).format = if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+                this.format.as_ptr() as *mut _
+            } else {
+                std::ptr::null_mut()
+            };
+            (*view).ndim = this.shape.len() as c_int;
+            (*view).shape = if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+                shape
+            } else {
+                std::ptr::null_mut()
+            };
+            (*view).strides = if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+                strides
+            } else {
+                std::ptr::null_mut()
+            };
+            (*view).suboffsets = std::ptr::null_mut();
+            (*view).internal = std::ptr::null_mut();
+
+            this.exports.set(this.exports.get() + 1);
+            count_export(this.buf, this.len(), 1);
+            drop(this);
+            (*view).obj = slf.into_any().into_ptr();
+        }
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, view: *mut ffi::Py_buffer) {
+        self.exports.set(self.exports.get().saturating_sub(1));
+        unsafe { count_export((*view).buf, (*view).len, -1) };
+    }
+}
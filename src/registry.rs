@@ -1,6 +1,10 @@
-use crate::{world_ref, BevyPyComp, BevyPyRes, DowncastReflect, GetTypeHash};
+use crate::{
+    columns::{ReadArrowFn, ReadColumnsFn, WriteColumnsFn},
+    world_ref, BevyPyColumns, BevyPyComp, BevyPyRes, ComponentColumns, DowncastReflect,
+    GetTypeHash,
+};
 use bevy::prelude::*;
-use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
 use std::collections::HashMap;
 
 // All components and resources deriving #[py_bevy_component] and #[py_bevy_resource] will implement BevyPyComp and BevyPyRes
@@ -34,6 +38,9 @@ pub struct PyObjectRegistry {
     remove_comp_and_return_fns: HashMap<u128, RemoveCompAndReturnOwnedFromWorldFn>,
     built_in_has_comps: HashMap<u128, BevyEntHashCompFn>,
     build_in_insert_comps: HashMap<u128, BevyCompInsertFromBoundAny>,
+    read_columns_fns: HashMap<u128, ReadColumnsFn>,
+    write_columns_fns: HashMap<u128, WriteColumnsFn>,
+    read_arrow_fns: HashMap<u128, ReadArrowFn>,
 }
 impl PyObjectRegistry {
     pub fn new() -> Self {
@@ -46,6 +53,9 @@ impl PyObjectRegistry {
             remove_comp_and_return_fns: HashMap::new(),
             built_in_has_comps: HashMap::new(),
             build_in_insert_comps: HashMap::new(),
+            read_columns_fns: HashMap::new(),
+            write_columns_fns: HashMap::new(),
+            read_arrow_fns: HashMap::new(),
         }
     }
     pub fn register_res<T: GetTypeHash + BevyPyRes + DowncastReflect>(&mut self) {
@@ -70,6 +80,16 @@ impl PyObjectRegistry {
         self.build_in_insert_comps
             .insert(hash, T::insert_into_world_from_bound_any);
     }
+    /// Register a component along with batch access to its `#[py_bevy(column)]` fields
+    pub fn register_comp_columns<T: GetTypeHash + BevyPyComp + BevyPyColumns + DowncastReflect>(
+        &mut self,
+    ) {
+        self.register_comp::<T>();
+        let hash = T::get_type_hash();
+        self.read_columns_fns.insert(hash, T::read_columns);
+        self.write_columns_fns.insert(hash, T::write_columns);
+        self.read_arrow_fns.insert(hash, T::read_arrow);
+    }
     pub fn create_bevy_res_ref<'py>(
         &self,
         py: Python<'py>,
@@ -150,6 +170,54 @@ impl PyObjectRegistry {
         rm_comp(py, &mut world, entity).ok()
     }
 
+    /// Entity ids and numpy arrays of `fields` for every entity with the component
+    pub fn read_comp_columns(
+        &self,
+        py: Python<'_>,
+        type_hash: u128,
+        world: world_ref::UnsafeWorldRef,
+        fields: &[String],
+    ) -> Option<PyResult<(Py<PyAny>, Py<PyDict>)>> {
+        let read_columns = self.read_columns_fns.get(&type_hash)?;
+        Some(read_columns(py, world, fields))
+    }
+    /// Write a dict of field name to array back into `entities`, row by row
+    pub fn write_comp_columns(
+        &self,
+        type_hash: u128,
+        world: world_ref::UnsafeWorldRef,
+        entities: &Bound<'_, PyAny>,
+        columns: &Bound<'_, PyDict>,
+    ) -> Option<PyResult<()>> {
+        let write_columns = self.write_columns_fns.get(&type_hash)?;
+        Some(write_columns(world, entities, columns))
+    }
+
+    /// Arrow record batch of the component's column fields for every entity with the component
+    pub fn read_comp_arrow(
+        &self,
+        py: Python<'_>,
+        type_hash: u128,
+        world: world_ref::UnsafeWorldRef,
+    ) -> Option<PyResult<Py<PyAny>>> {
+        let read_arrow = self.read_arrow_fns.get(&type_hash)?;
+        Some(read_arrow(py, world))
+    }
+
+    /// Python access to the column reads and writes of a component registered with register_comp_columns
+    pub fn comp_columns(
+        &self,
+        type_hash: u128,
+        world: world_ref::UnsafeWorldRef,
+    ) -> Option<ComponentColumns> {
+        Some(ComponentColumns::new(
+            world,
+            *self.read_columns_fns.get(&type_hash)?,
+            *self.write_columns_fns.get(&type_hash)?,
+            *self.read_arrow_fns.get(&type_hash)?,
+        ))
+    }
+
     pub fn insert_comp_from_py_any_bound(
         &mut self,
         comp: Bound<'_, PyAny>,
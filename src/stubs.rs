@@ -0,0 +1,28 @@
+use pyo3_stub_gen::{PyStubType, TypeInfo};
+
+/// A `type[T]` type annotation, for functions taking a python class
+pub fn stub_type_of<T: PyStubType>() -> TypeInfo {
+    let mut inner = T::type_output();
+    inner.import.insert("builtins".into());
+
+    // the inner type still needs to be qualified when put in another module
+    if let Some(source_module) = &inner.source_module {
+        if source_module.get().is_some() {
+            let bare_name = inner.name.split('.').next_back().unwrap_or(&inner.name);
+            inner.type_refs.insert(
+                bare_name.to_string(),
+                pyo3_stub_gen::TypeIdentifierRef {
+                    module: source_module.clone(),
+                    import_kind: pyo3_stub_gen::ImportKind::Module,
+                },
+            );
+        }
+    }
+
+    TypeInfo {
+        name: format!("builtins.type[{}]", inner.name),
+        source_module: None,
+        import: inner.import,
+        type_refs: inner.type_refs,
+    }
+}
@@ -0,0 +1,184 @@
+use pyo3::{buffer::PyBuffer, exceptions::PyValueError, prelude::*, types::PyDict};
+use simple_py_bevy::*;
+
+fn non_negative(mass: &f32) -> PyResult<()> {
+    match *mass < 0.0 {
+        true => Err(PyValueError::new_err("mass can't be negative")),
+        false => Ok(()),
+    }
+}
+
+#[derive(Clone, Component, PyBevyCompRef, Default)]
+#[pyclass]
+struct Particle {
+    #[py_bevy(column, numpy)]
+    pos: Vec3,
+    #[py_bevy(column, validate = non_negative)]
+    mass: f32,
+}
+#[py_bevy_methods]
+#[pymethods]
+impl Particle {}
+
+const N: usize = 4;
+
+fn spawn_particles() -> (App, Vec<Entity>) {
+    let mut app = App::new();
+    app.add_plugins(PyBevyPlugin);
+    let world = app.world_mut();
+    world
+        .resource_mut::<PyObjectRegistry>()
+        .register_comp_columns::<Particle>();
+    let entities = (0..N)
+        .map(|i| {
+            let pos = Vec3::new(i as f32, 10.0 * i as f32, 100.0 * i as f32);
+            world.spawn(Particle { pos, mass: 1.0 }).id()
+        })
+        .collect();
+    (app, entities)
+}
+
+fn with_python(f: impl FnOnce(Python<'_>)) {
+    Python::initialize();
+    Python::attach(|py| {
+        // column_to_numpy only needs numpy.asarray, memoryview has the same buffer
+        if py.import("numpy").is_err() {
+            let numpy = py
+                .import("types")
+                .unwrap()
+                .call_method1("SimpleNamespace", ())
+                .unwrap();
+            numpy
+                .setattr("asarray", py.eval(c"memoryview", None, None).unwrap())
+                .unwrap();
+            py.import("sys")
+                .unwrap()
+                .getattr("modules")
+                .unwrap()
+                .set_item("numpy", numpy)
+                .unwrap();
+        }
+        f(py)
+    })
+}
+
+fn read(py: Python<'_>, app: &mut App, field: &str) -> (Vec<u64>, Vec<usize>, Vec<f32>) {
+    let world = app.world_mut();
+    let world_ref = UnsafeWorldRef::new(world);
+    let (ids, columns) = world
+        .resource::<PyObjectRegistry>()
+        .read_comp_columns(
+            py,
+            Particle::get_type_hash(),
+            world_ref,
+            &[field.to_string()],
+        )
+        .unwrap()
+        .unwrap();
+
+    let ids: Vec<u64> = PyBuffer::get(ids.bind(py)).unwrap().to_vec(py).unwrap();
+    let column = columns.bind(py).get_item(field).unwrap().unwrap();
+    let buffer = PyBuffer::<f32>::get(&column).unwrap();
+    (ids, buffer.shape().to_vec(), buffer.to_vec(py).unwrap())
+}
+
+fn write(
+    py: Python<'_>,
+    app: &mut App,
+    ids: &[u64],
+    columns: Vec<(&str, Vec<f32>, &[usize])>,
+) -> PyResult<()> {
+    let dict = PyDict::new(py);
+    for (field, data, shape) in columns {
+        let column = BufferView::from_vec(data, shape)?.into_numpy(py)?;
+        dict.set_item(field, column)?;
+    }
+    let ids = ids.to_vec().into_pyobject(py)?;
+
+    let world = app.world_mut();
+    let world_ref = UnsafeWorldRef::new(world);
+    world
+        .resource::<PyObjectRegistry>()
+        .write_comp_columns(Particle::get_type_hash(), world_ref, &ids, &dict)
+        .unwrap()
+}
+
+fn positions(app: &App, entities: &[Entity]) -> Vec<Vec3> {
+    entities
+        .iter()
+        .map(|e| app.world().get::<Particle>(*e).unwrap().pos)
+        .collect()
+}
+
+#[test]
+fn read_and_write_back() {
+    with_python(|py| {
+        let (mut app, _) = spawn_particles();
+        let (ids, shape, data) = read(py, &mut app, "pos");
+        assert_eq!(shape, [N, 3]);
+
+        let rows: Vec<Entity> = ids.iter().map(|id| Entity::from_bits(*id)).collect();
+        for (row, entity) in rows.iter().enumerate() {
+            let pos = app.world().get::<Particle>(*entity).unwrap().pos;
+            assert_eq!(data[row * 3..(row + 1) * 3], pos.to_array());
+        }
+
+        let moved: Vec<f32> = data.iter().map(|x| x + 0.5).collect();
+        write(py, &mut app, &ids, vec![("pos", moved.clone(), &[N, 3])]).unwrap();
+        for (row, entity) in rows.iter().enumerate() {
+            let pos = app.world().get::<Particle>(*entity).unwrap().pos;
+            assert_eq!(pos.to_array(), moved[row * 3..(row + 1) * 3]);
+        }
+        assert_eq!(read(py, &mut app, "pos").2, moved);
+        let (_, _, masses) = read(py, &mut app, "mass");
+        assert_eq!(masses, [1.0; N]);
+    });
+}
+
+#[test]
+fn invalid_writes_write_nothing() {
+    with_python(|py| {
+        let (mut app, entities) = spawn_particles();
+        let (ids, _, data) = read(py, &mut app, "pos");
+        let before = positions(&app, &entities);
+        let moved: Vec<f32> = data.iter().map(|x| x + 0.5).collect();
+
+        // a despawned entity in any row
+        let mut missing = ids.clone();
+        let gone = app.world_mut().spawn_empty().id();
+        app.world_mut().despawn(gone);
+        missing[N - 1] = gone.to_bits();
+        let err = write(
+            py,
+            &mut app,
+            &missing,
+            vec![("pos", moved.clone(), &[N, 3])],
+        );
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("doesn't have the component"));
+        assert_eq!(positions(&app, &entities), before);
+
+        // one row short
+        let short = moved[..(N - 1) * 3].to_vec();
+        let err = write(py, &mut app, &ids, vec![("pos", short, &[N - 1, 3])]);
+        assert!(err.unwrap_err().to_string().contains("Expected 4 rows"));
+        assert_eq!(positions(&app, &entities), before);
+
+        // a row failing the field's validate stops every column
+        let masses = vec![1.0, 2.0, -1.0, 3.0];
+        let err = write(
+            py,
+            &mut app,
+            &ids,
+            vec![("pos", moved.clone(), &[N, 3]), ("mass", masses, &[N])],
+        );
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("mass can't be negative"));
+        assert_eq!(positions(&app, &entities), before);
+        assert_eq!(read(py, &mut app, "mass").2, [1.0; N]);
+    });
+}
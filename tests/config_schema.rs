@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use simple_py_bevy::{root_config_schema, validate_config_schema, ConfigSchema, ConfigValue};
+
+#[derive(Deserialize, Serialize, Default, ConfigSchema)]
+#[serde(rename_all = "camelCase")]
+struct Layer {
+    /// Name shown in logs
+    layer_name: String,
+    #[serde(default)]
+    opacity: f32,
+}
+
+#[derive(Deserialize, Serialize, ConfigSchema)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    Fast,
+    Slow,
+}
+
+/// Test app config
+#[derive(Deserialize, Serialize, ConfigSchema)]
+struct AppConfig {
+    base: Layer,
+    overlay: Option<Layer>,
+    layers: Vec<Layer>,
+    modes: Vec<Option<Mode>>,
+}
+
+fn yaml(contents: &str) -> ConfigValue {
+    serde_yaml::from_str(contents).unwrap()
+}
+
+fn errors(contents: &str) -> Vec<String> {
+    let mut errors =
+        validate_config_schema(&root_config_schema::<AppConfig>("App"), &yaml(contents));
+    errors.sort();
+    errors
+}
+
+#[test]
+fn nested_option_and_vec_schema() {
+    let schema = root_config_schema::<AppConfig>("App");
+    assert_eq!(schema["title"], yaml("App"));
+    assert_eq!(schema["description"], yaml("Test app config"));
+    assert_eq!(schema["required"], yaml("[base, layers, modes]"));
+
+    let base = &schema["properties"]["base"];
+    assert_eq!(base["required"], yaml("[layerName]"));
+    assert_eq!(
+        base["properties"]["layerName"]["description"],
+        yaml("Name shown in logs")
+    );
+    assert_eq!(base["properties"]["opacity"]["default"], yaml("0.0"));
+    assert_eq!(
+        schema["properties"]["layers"]["items"],
+        Layer::config_schema()
+    );
+}
+
+#[test]
+fn valid_config() {
+    let contents = "
+        base: {layerName: ground}
+        overlay: null
+        layers: [{layerName: sky, opacity: 0.5}]
+        modes: [fast, null]
+    ";
+    assert!(errors(contents).is_empty());
+    serde_yaml::from_value::<AppConfig>(yaml(contents)).unwrap();
+}
+
+#[test]
+fn nested_option_and_vec_errors() {
+    let contents = "
+        base: {layer_name: ground}
+        overlay: {layerName: 1, opacity: high}
+        layers: [{layerName: sky}, {}]
+        modes: [medium]
+    ";
+    assert_eq!(
+        errors(contents),
+        [
+            "base.layer_name: unknown field",
+            "base: missing field layerName",
+            "layers[1]: missing field layerName",
+            "modes[0]: expected one of fast, slow, got medium",
+            "overlay.layerName: expected string, got 1",
+            "overlay.opacity: expected number, got high",
+        ]
+    );
+}
+
+#[derive(Deserialize, Serialize, ConfigSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Server {
+    HTTPServer,
+    Mode2D,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, ConfigSchema)]
+#[serde(rename_all = "camelCase")]
+struct Names {
+    my_fieldA: u32,
+    server: Server,
+}
+
+#[test]
+fn rename_all_matches_serde() {
+    for server in [Server::HTTPServer, Server::Mode2D] {
+        let value = serde_yaml::to_value(Names {
+            my_fieldA: 1,
+            server,
+        })
+        .unwrap();
+        assert!(validate_config_schema(&root_config_schema::<Names>("Names"), &value).is_empty());
+    }
+    let schema = Names::config_schema();
+    assert_eq!(schema["required"], yaml("[myFieldA, server]"));
+    assert_eq!(
+        Server::config_schema()["enum"],
+        yaml("[H_T_T_P_SERVER, MODE2_D]")
+    );
+}